@@ -0,0 +1,180 @@
+#![warn(clippy::pedantic)]
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use tokio::sync::{broadcast, Mutex};
+use tracing::{error, info, warn};
+
+mod player;
+mod stations;
+
+use player::Player;
+
+struct Server {
+    player: Mutex<Player>,
+    diffs: broadcast::Sender<rradio_messages::PlayerStateDiff>,
+}
+
+impl Server {
+    /// Broadcast any changes to the player to all clients
+    fn publish_changes(&self, player: &mut Player) {
+        if let Some(diff) = player.take_diff() {
+            // There might not be any clients connected
+            let _ = self.diffs.send(diff);
+        }
+    }
+}
+
+const USAGE: &str = "Usage: rradio-mock-server [--port PORT] [--dist DIRECTORY]";
+
+struct Args {
+    port: u16,
+    dist: PathBuf,
+}
+
+impl Args {
+    fn parse() -> anyhow::Result<Self> {
+        let mut args = Self {
+            port: 8000,
+            dist: PathBuf::from("dist"),
+        };
+
+        let mut env_args = std::env::args().skip(1);
+
+        while let Some(arg) = env_args.next() {
+            let mut value = || {
+                env_args
+                    .next()
+                    .with_context(|| format!("Missing value for {arg}"))
+            };
+
+            match arg.as_str() {
+                "--port" => args.port = value()?.parse().context("Invalid port")?,
+                "--dist" => args.dist = value()?.into(),
+                _ => anyhow::bail!("Unknown argument {arg:?}. {USAGE}"),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn encode_event(diff: rradio_messages::PlayerStateDiff) -> anyhow::Result<Message> {
+    let mut buffer = Vec::new();
+
+    rradio_messages::Event::PlayerStateChanged(diff)
+        .encode(&mut buffer)
+        .context("Failed to encode Event")?;
+
+    Ok(Message::Binary(buffer))
+}
+
+async fn handle_client(mut socket: WebSocket, server: Arc<Server>) -> anyhow::Result<()> {
+    let (full_state, mut diffs) = {
+        let player = server.player.lock().await;
+        (player.full_state(), server.diffs.subscribe())
+    };
+
+    // Announce the API version, so that clients which request a different version can tell why they were rejected
+    socket
+        .send(Message::Text(
+            rradio_messages::API_VERSION_HEADER.trim().into(),
+        ))
+        .await?;
+
+    socket.send(encode_event(full_state)?).await?;
+
+    loop {
+        tokio::select! {
+            diff = diffs.recv() => {
+                let diff = match diff {
+                    Ok(diff) => diff,
+                    Err(broadcast::error::RecvError::Lagged(_)) => server.player.lock().await.full_state(),
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                };
+
+                socket.send(encode_event(diff)?).await?;
+            }
+            message = socket.recv() => {
+                match message {
+                    None | Some(Ok(Message::Close(_))) => return Ok(()),
+                    Some(Ok(Message::Binary(mut buffer))) => {
+                        let command = rradio_messages::Command::decode(&mut buffer)
+                            .context("Failed to decode Command")?;
+
+                        info!("Command: {command:?}");
+
+                        let mut player = server.player.lock().await;
+                        player.handle_command(command);
+                        server.publish_changes(&mut player);
+                    }
+                    Some(Ok(message)) => warn!("Ignoring message: {message:?}"),
+                    Some(Err(err)) => return Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+async fn api(ws: WebSocketUpgrade, State(server): State<Arc<Server>>) -> Response {
+    ws.protocols([rradio_messages::API_VERSION_HEADER.trim()])
+        .on_upgrade(|socket| async move {
+            info!("Client connected");
+
+            match handle_client(socket, server).await {
+                Ok(()) => info!("Client disconnected"),
+                Err(err) => error!("Client error: {err:#}"),
+            }
+        })
+}
+
+async fn run_player(server: Arc<Server>) {
+    let mut ticks = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        ticks.tick().await;
+
+        let mut player = server.player.lock().await;
+        player.tick();
+        server.publish_changes(&mut player);
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let Args { port, dist } = Args::parse()?;
+
+    let server = Arc::new(Server {
+        player: Mutex::new(Player::new()),
+        diffs: broadcast::channel(64).0,
+    });
+
+    tokio::spawn(run_player(server.clone()));
+
+    let app = Router::new()
+        .route("/api", get(api))
+        .fallback_service(tower_http::services::ServeDir::new(&dist))
+        .with_state(server);
+
+    let address = SocketAddr::from(([0, 0, 0, 0], port));
+
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .with_context(|| format!("Failed to listen on {address}"))?;
+
+    info!("Serving {} on http://{address}", dist.display());
+
+    axum::serve(listener, app).await.context("Server failed")
+}
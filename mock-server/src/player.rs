@@ -0,0 +1,652 @@
+use std::time::Duration;
+
+use rradio_messages::{
+    Command, CurrentStation, LatestError, PingTimes, PipelineState, PlayerStateDiff, StationType,
+    Track, TrackTags,
+};
+use tracing::warn;
+
+use crate::stations::{self, MockStation, MockTrack};
+
+const VOLUME_STEP: i32 = 5;
+const SMART_PREVIOUS_THRESHOLD: Duration = Duration::from_secs(2);
+const HEARTBEAT_TICKS: u32 = 10;
+
+fn empty_diff() -> PlayerStateDiff {
+    PlayerStateDiff {
+        pipeline_state: None,
+        current_station: None,
+        pause_before_playing: None,
+        current_track_index: None,
+        current_track_tags: None,
+        is_muted: None,
+        volume: None,
+        buffering: None,
+        track_duration: None,
+        track_position: None,
+        ping_times: None,
+        latest_error: None,
+    }
+}
+
+fn is_empty(diff: &PlayerStateDiff) -> bool {
+    let PlayerStateDiff {
+        pipeline_state,
+        current_station,
+        pause_before_playing,
+        current_track_index,
+        current_track_tags,
+        is_muted,
+        volume,
+        buffering,
+        track_duration,
+        track_position,
+        ping_times,
+        latest_error,
+    } = diff;
+
+    pipeline_state.is_none()
+        && current_station.is_none()
+        && pause_before_playing.is_none()
+        && current_track_index.is_none()
+        && current_track_tags.is_none()
+        && is_muted.is_none()
+        && volume.is_none()
+        && buffering.is_none()
+        && track_duration.is_none()
+        && track_position.is_none()
+        && ping_times.is_none()
+        && latest_error.is_none()
+}
+
+/// A simulated rradio player, which plays scripted stations without any audio
+pub struct Player {
+    stations: Vec<MockStation>,
+    pipeline_state: PipelineState,
+    current_station: Option<MockStation>,
+    /// Why the last station failed to play, if it did
+    station_error: Option<rradio_messages::Error>,
+    current_track_index: usize,
+    is_muted: bool,
+    volume: i32,
+    buffering: u8,
+    track_position: Option<Duration>,
+    latest_error: Option<LatestError>,
+    ticks_since_heartbeat: u32,
+    diff: PlayerStateDiff,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        let stations = stations::scripted_stations();
+        let first_station = stations.first().cloned();
+
+        let mut player = Self {
+            stations,
+            pipeline_state: PipelineState::Null,
+            current_station: None,
+            station_error: None,
+            current_track_index: 0,
+            is_muted: false,
+            volume: rradio_messages::VOLUME_ZERO_DB - 30,
+            buffering: 0,
+            track_position: None,
+            latest_error: None,
+            ticks_since_heartbeat: 0,
+            diff: empty_diff(),
+        };
+
+        if let Some(station) = first_station {
+            player.play_station(station);
+        }
+
+        player
+    }
+
+    fn current_track(&self) -> Option<&MockTrack> {
+        self.current_station
+            .as_ref()?
+            .tracks
+            .get(self.current_track_index)
+    }
+
+    fn current_station_message(&self) -> CurrentStation {
+        match (&self.current_station, &self.station_error) {
+            (None, None) => CurrentStation::NoStation,
+            (None, Some(error)) => CurrentStation::FailedToPlayStation {
+                error: error.clone(),
+            },
+            // Stations are selected by comparing the displayed index, so none is sent
+            (Some(station), _) => CurrentStation::PlayingStation {
+                index: None,
+                source_type: StationType::UrlList,
+                title: Some(station.title.clone()),
+                tracks: Some(
+                    station
+                        .tracks
+                        .iter()
+                        .map(|track| Track {
+                            title: Some(track.title.clone()),
+                            album: track.album.clone(),
+                            artist: track.artist.clone(),
+                            url: track.url.clone(),
+                            is_notification: false,
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            },
+        }
+    }
+
+    fn current_track_tags(&self) -> Option<TrackTags> {
+        self.current_track().map(|track| TrackTags {
+            title: Some(track.title.clone()),
+            organisation: None,
+            artist: track.artist.clone(),
+            album: track.album.clone(),
+            genre: None,
+            image: None,
+            comment: None,
+        })
+    }
+
+    fn track_duration(&self) -> Option<Duration> {
+        self.current_track().and_then(|track| track.duration)
+    }
+
+    /// The entire state, sent to clients when they connect
+    pub fn full_state(&self) -> PlayerStateDiff {
+        PlayerStateDiff {
+            pipeline_state: Some(self.pipeline_state),
+            current_station: Some(self.current_station_message()),
+            pause_before_playing: Some(None),
+            current_track_index: Some(self.current_track_index),
+            current_track_tags: Some(self.current_track_tags()),
+            is_muted: Some(self.is_muted),
+            volume: Some(self.volume),
+            buffering: Some(self.buffering),
+            track_duration: Some(self.track_duration()),
+            track_position: Some(self.track_position),
+            ping_times: Some(PingTimes::default()),
+            latest_error: Some(self.latest_error.clone()),
+        }
+    }
+
+    /// The changes since the last call, if there are any
+    pub fn take_diff(&mut self) -> Option<PlayerStateDiff> {
+        let diff = std::mem::replace(&mut self.diff, empty_diff());
+
+        (!is_empty(&diff)).then_some(diff)
+    }
+
+    fn set_pipeline_state(&mut self, pipeline_state: PipelineState) {
+        self.pipeline_state = pipeline_state;
+        self.diff.pipeline_state = Some(pipeline_state);
+    }
+
+    fn set_buffering(&mut self, buffering: u8) {
+        self.buffering = buffering;
+        self.diff.buffering = Some(buffering);
+    }
+
+    fn set_track_position(&mut self, track_position: Option<Duration>) {
+        self.track_position = track_position;
+        self.diff.track_position = Some(track_position);
+    }
+
+    fn set_volume(&mut self, volume: i32) {
+        self.volume = volume.clamp(rradio_messages::VOLUME_MIN, rradio_messages::VOLUME_ZERO_DB);
+        self.diff.volume = Some(self.volume);
+    }
+
+    fn set_error(&mut self, error: rradio_messages::Error) {
+        warn!("Simulated error: {error}");
+
+        self.latest_error = Some(LatestError {
+            timestamp: chrono::Utc::now(),
+            error,
+        });
+        self.diff.latest_error = Some(self.latest_error.clone());
+    }
+
+    fn play_station(&mut self, station: MockStation) {
+        if station.tracks.is_empty() {
+            let error = rradio_messages::Error::NoPlaylist;
+
+            self.stop();
+            self.station_error = Some(error.clone());
+            self.diff.current_station = Some(self.current_station_message());
+            self.set_error(error);
+
+            return;
+        }
+
+        self.current_station = Some(station);
+        self.station_error = None;
+        self.diff.current_station = Some(self.current_station_message());
+
+        self.play_track(0);
+    }
+
+    fn play_track(&mut self, track_index: usize) {
+        self.current_track_index = track_index;
+        self.diff.current_track_index = Some(track_index);
+        self.diff.current_track_tags = Some(self.current_track_tags());
+        self.diff.track_duration = Some(self.track_duration());
+
+        self.set_track_position(Some(Duration::ZERO));
+        self.set_buffering(0);
+        self.set_pipeline_state(PipelineState::Playing);
+    }
+
+    fn stop(&mut self) {
+        self.current_station = None;
+        self.station_error = None;
+        self.diff.current_station = Some(CurrentStation::NoStation);
+        self.current_track_index = 0;
+        self.diff.current_track_index = Some(0);
+        self.diff.current_track_tags = Some(None);
+        self.diff.track_duration = Some(None);
+
+        self.set_track_position(None);
+        self.set_buffering(0);
+        self.set_pipeline_state(PipelineState::Null);
+    }
+
+    fn track_count(&self) -> usize {
+        self.current_station
+            .as_ref()
+            .map_or(0, |station| station.tracks.len())
+    }
+
+    fn next_track(&mut self) {
+        let track_count = self.track_count();
+
+        if track_count > 0 {
+            self.play_track((self.current_track_index + 1) % track_count);
+        }
+    }
+
+    fn previous_track(&mut self) {
+        let track_count = self.track_count();
+
+        if track_count > 0 {
+            self.play_track(
+                self.current_track_index
+                    .checked_sub(1)
+                    .unwrap_or(track_count - 1),
+            );
+        }
+    }
+
+    /// Live streams can't be seeked
+    fn seek(&mut self, seek: impl FnOnce(Duration) -> Duration) {
+        let (Some(track_position), Some(track_duration)) =
+            (self.track_position, self.track_duration())
+        else {
+            return;
+        };
+
+        self.set_track_position(Some(seek(track_position).min(track_duration)));
+    }
+
+    // Newer versions of the protocol might have commands which aren't simulated
+    #[allow(clippy::match_wildcard_for_single_variants)]
+    pub fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::SetChannel(index) => {
+                let index = index.to_string();
+
+                if let Some(station) = self
+                    .stations
+                    .iter()
+                    .find(|station| station.index == Some(index.as_str()))
+                    .cloned()
+                {
+                    self.play_station(station);
+                } else {
+                    self.set_error(rradio_messages::Error::NoPlaylist);
+                }
+            }
+            Command::PlayPause => match self.pipeline_state {
+                PipelineState::Playing => self.set_pipeline_state(PipelineState::Paused),
+                PipelineState::Paused => self.set_pipeline_state(PipelineState::Playing),
+                _ => (),
+            },
+            Command::SmartPreviousItem => {
+                if self
+                    .track_position
+                    .is_some_and(|track_position| track_position > SMART_PREVIOUS_THRESHOLD)
+                {
+                    self.seek(|_| Duration::ZERO);
+                } else {
+                    self.previous_track();
+                }
+            }
+            Command::PreviousItem => self.previous_track(),
+            Command::NextItem => self.next_track(),
+            Command::NthItem(track_index) => {
+                if track_index < self.track_count() {
+                    self.play_track(track_index);
+                } else {
+                    self.set_error(rradio_messages::Error::InvalidTrackIndex(track_index));
+                }
+            }
+            Command::SeekTo(position) => self.seek(|_| position),
+            Command::SeekBackwards(offset) => {
+                self.seek(|position| position.saturating_sub(offset));
+            }
+            Command::SeekForwards(offset) => self.seek(|position| position + offset),
+            Command::VolumeUp => self.set_volume(self.volume + VOLUME_STEP),
+            Command::VolumeDown => self.set_volume(self.volume - VOLUME_STEP),
+            Command::SetVolume(volume) => self.set_volume(volume),
+            Command::SetIsMuted(is_muted) => {
+                self.is_muted = is_muted;
+                self.diff.is_muted = Some(is_muted);
+            }
+            Command::PlayUrl(url) => self.play_station(stations::url(url)),
+            Command::SetPlaylist { title, tracks } => {
+                self.play_station(stations::playlist(title, tracks));
+            }
+            Command::Eject => self.stop(),
+            command => warn!("Ignoring unsupported command: {command:?}"),
+        }
+    }
+
+    /// Advance the simulation by one second
+    pub fn tick(&mut self) {
+        self.ticks_since_heartbeat += 1;
+
+        // The real server regularly sends ping times, which clients use to detect dead connections
+        if self.ticks_since_heartbeat >= HEARTBEAT_TICKS {
+            self.ticks_since_heartbeat = 0;
+            self.diff.ping_times = Some(PingTimes::default());
+        }
+
+        if self.pipeline_state != PipelineState::Playing {
+            return;
+        }
+
+        if self.buffering < 100 {
+            self.set_buffering(self.buffering.saturating_add(50).min(100));
+            return;
+        }
+
+        let Some(track_position) = self.track_position else {
+            return;
+        };
+
+        let track_position = track_position + Duration::from_secs(1);
+
+        if self
+            .track_duration()
+            .is_some_and(|track_duration| track_position >= track_duration)
+        {
+            self.next_track();
+        } else {
+            self.set_track_position(Some(track_position));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    /// A player with the changes from starting the first station already taken
+    fn player() -> Player {
+        let mut player = Player::new();
+        player.take_diff();
+        player
+    }
+
+    fn set_channel(player: &mut Player, index: &str) {
+        player.handle_command(Command::SetChannel(String::from(index).into()));
+    }
+
+    fn latest_error(player: &Player) -> Option<&rradio_messages::Error> {
+        player
+            .latest_error
+            .as_ref()
+            .map(|latest_error| &latest_error.error)
+    }
+
+    /// Tick until buffering has finished
+    fn buffer(player: &mut Player) {
+        while player.buffering < 100 {
+            player.tick();
+        }
+    }
+
+    #[test]
+    fn starts_playing_the_first_station() {
+        let player = Player::new();
+
+        assert_eq!(player.pipeline_state, PipelineState::Playing);
+        assert_eq!(player.current_track_index, 0);
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+        assert_eq!(player.track_duration(), Some(secs(95)));
+    }
+
+    #[test]
+    fn seeks_are_clamped_to_the_track() {
+        let mut player = player();
+
+        player.handle_command(Command::SeekTo(secs(1000)));
+        assert_eq!(player.track_position, Some(secs(95)));
+
+        player.handle_command(Command::SeekBackwards(secs(30)));
+        assert_eq!(player.track_position, Some(secs(65)));
+
+        player.handle_command(Command::SeekForwards(secs(60)));
+        assert_eq!(player.track_position, Some(secs(95)));
+
+        player.handle_command(Command::SeekBackwards(secs(1000)));
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn live_streams_are_not_seeked() {
+        let mut player = player();
+
+        set_channel(&mut player, "02");
+        assert_eq!(player.track_duration(), None);
+
+        player.handle_command(Command::SeekTo(secs(30)));
+        player.handle_command(Command::SeekForwards(secs(30)));
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn smart_previous_restarts_the_track_after_the_threshold() {
+        let mut player = player();
+
+        player.handle_command(Command::NthItem(1));
+        player.handle_command(Command::SeekTo(SMART_PREVIOUS_THRESHOLD + secs(1)));
+
+        player.handle_command(Command::SmartPreviousItem);
+        assert_eq!(player.current_track_index, 1);
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+
+        player.handle_command(Command::SeekTo(SMART_PREVIOUS_THRESHOLD));
+
+        player.handle_command(Command::SmartPreviousItem);
+        assert_eq!(player.current_track_index, 0);
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut player = player();
+
+        player.handle_command(Command::PreviousItem);
+        assert_eq!(player.current_track_index, 2);
+
+        player.handle_command(Command::NextItem);
+        assert_eq!(player.current_track_index, 0);
+
+        player.handle_command(Command::NextItem);
+        assert_eq!(player.current_track_index, 1);
+    }
+
+    #[test]
+    fn out_of_range_track_is_an_error() {
+        let mut player = player();
+
+        player.handle_command(Command::NthItem(2));
+        assert_eq!(player.current_track_index, 2);
+        assert!(latest_error(&player).is_none());
+
+        player.handle_command(Command::NthItem(3));
+        assert_eq!(player.current_track_index, 2);
+        assert!(matches!(
+            latest_error(&player),
+            Some(rradio_messages::Error::InvalidTrackIndex(3))
+        ));
+    }
+
+    #[test]
+    fn buffers_before_advancing() {
+        let mut player = player();
+
+        player.tick();
+        assert_eq!(player.buffering, 50);
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+
+        player.tick();
+        assert_eq!(player.buffering, 100);
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+
+        player.tick();
+        assert_eq!(player.track_position, Some(secs(1)));
+    }
+
+    #[test]
+    fn paused_player_does_not_advance() {
+        let mut player = player();
+        buffer(&mut player);
+
+        player.handle_command(Command::PlayPause);
+        assert_eq!(player.pipeline_state, PipelineState::Paused);
+
+        player.tick();
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+
+        player.handle_command(Command::PlayPause);
+        assert_eq!(player.pipeline_state, PipelineState::Playing);
+
+        player.tick();
+        assert_eq!(player.track_position, Some(secs(1)));
+    }
+
+    #[test]
+    fn advances_to_the_next_track_at_the_end() {
+        let mut player = player();
+
+        player.handle_command(Command::NthItem(1));
+        player.handle_command(Command::SeekTo(secs(19)));
+        buffer(&mut player);
+
+        player.tick();
+        assert_eq!(player.current_track_index, 2);
+        assert_eq!(player.track_position, Some(Duration::ZERO));
+        assert_eq!(player.buffering, 0);
+    }
+
+    #[test]
+    fn station_without_tracks_fails_to_play() {
+        let mut player = player();
+
+        set_channel(&mut player, "03");
+
+        assert_eq!(player.pipeline_state, PipelineState::Null);
+        assert!(matches!(
+            player.current_station_message(),
+            CurrentStation::FailedToPlayStation {
+                error: rradio_messages::Error::NoPlaylist
+            }
+        ));
+        assert!(matches!(
+            latest_error(&player),
+            Some(rradio_messages::Error::NoPlaylist)
+        ));
+
+        player.handle_command(Command::SetPlaylist {
+            title: String::from("Empty"),
+            tracks: Vec::new(),
+        });
+        assert!(matches!(
+            player.current_station_message(),
+            CurrentStation::FailedToPlayStation { .. }
+        ));
+
+        set_channel(&mut player, "01");
+        assert!(matches!(
+            player.current_station_message(),
+            CurrentStation::PlayingStation { .. }
+        ));
+    }
+
+    #[test]
+    fn unknown_station_is_an_error() {
+        let mut player = player();
+
+        set_channel(&mut player, "99");
+
+        assert_eq!(player.pipeline_state, PipelineState::Playing);
+        assert!(matches!(
+            latest_error(&player),
+            Some(rradio_messages::Error::NoPlaylist)
+        ));
+    }
+
+    #[test]
+    fn diffs_accumulate_until_taken() {
+        let mut player = player();
+        assert!(player.take_diff().is_none());
+
+        player.handle_command(Command::SetVolume(50));
+        player.handle_command(Command::SetIsMuted(true));
+        player.handle_command(Command::VolumeUp);
+
+        let diff = player.take_diff().unwrap();
+        assert_eq!(diff.volume, Some(50 + VOLUME_STEP));
+        assert_eq!(diff.is_muted, Some(true));
+        assert_eq!(diff.pipeline_state, None);
+        assert_eq!(diff.track_position, None);
+
+        assert!(player.take_diff().is_none());
+    }
+
+    #[test]
+    fn volume_is_clamped() {
+        let mut player = player();
+
+        player.handle_command(Command::SetVolume(rradio_messages::VOLUME_MIN - 10));
+        assert_eq!(player.volume, rradio_messages::VOLUME_MIN);
+
+        player.handle_command(Command::SetVolume(rradio_messages::VOLUME_ZERO_DB));
+        player.handle_command(Command::VolumeUp);
+        assert_eq!(player.volume, rradio_messages::VOLUME_ZERO_DB);
+    }
+
+    #[test]
+    fn sends_a_heartbeat_when_idle() {
+        let mut player = player();
+        player.handle_command(Command::Eject);
+        player.take_diff();
+
+        for _ in 1..HEARTBEAT_TICKS {
+            player.tick();
+        }
+        assert!(player.take_diff().is_none());
+
+        player.tick();
+        assert!(player.take_diff().unwrap().ping_times.is_some());
+    }
+}
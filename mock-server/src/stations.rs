@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use rradio_messages::ArcStr;
+
+#[derive(Clone)]
+pub struct MockTrack {
+    pub title: ArcStr,
+    pub artist: Option<ArcStr>,
+    pub album: Option<ArcStr>,
+    pub url: ArcStr,
+    /// `None` for live streams, which never end
+    pub duration: Option<Duration>,
+}
+
+#[derive(Clone)]
+pub struct MockStation {
+    /// Compared with the displayed [`rradio_messages::StationIndex`] sent by clients
+    pub index: Option<&'static str>,
+    pub title: ArcStr,
+    /// Playing a station without any tracks fails
+    pub tracks: Vec<MockTrack>,
+}
+
+fn track(title: &str, artist: &str, album: &str, duration_secs: u64) -> MockTrack {
+    MockTrack {
+        title: title.into(),
+        artist: Some(artist.into()),
+        album: Some(album.into()),
+        url: format!("mock://{}", title.to_lowercase().replace(' ', "-")).into(),
+        duration: Some(Duration::from_secs(duration_secs)),
+    }
+}
+
+/// The stations which can be selected with [`rradio_messages::Command::SetChannel`]
+pub fn scripted_stations() -> Vec<MockStation> {
+    vec![
+        MockStation {
+            index: Some("01"),
+            title: "Mock Album".into(),
+            tracks: vec![
+                track("Opening", "The Mockers", "Mock Album", 95),
+                track("Interlude", "The Mockers", "Mock Album", 20),
+                track("Long Song", "The Mockers", "Mock Album", 3725),
+            ],
+        },
+        MockStation {
+            index: Some("02"),
+            title: "Mock Radio".into(),
+            tracks: vec![MockTrack {
+                title: "Mock Radio Live".into(),
+                artist: None,
+                album: None,
+                url: "mock://mock-radio".into(),
+                duration: None,
+            }],
+        },
+        MockStation {
+            index: Some("03"),
+            title: "Broken Station".into(),
+            tracks: Vec::new(),
+        },
+    ]
+}
+
+/// `Duration::from_mins` needs a more recent toolchain than the rest of the workspace
+#[allow(clippy::duration_suboptimal_units)]
+const PLAYLIST_TRACK_DURATION: Duration = Duration::from_secs(30 * 60);
+
+/// A station created by [`rradio_messages::Command::SetPlaylist`], e.g. from the podcasts page.
+/// Each track pretends to be [`PLAYLIST_TRACK_DURATION`] long.
+pub fn playlist(title: String, tracks: Vec<rradio_messages::SetPlaylistTrack>) -> MockStation {
+    MockStation {
+        index: None,
+        title: title.into(),
+        tracks: tracks
+            .into_iter()
+            .map(
+                |rradio_messages::SetPlaylistTrack { title, url }| MockTrack {
+                    title: title.into(),
+                    artist: None,
+                    album: None,
+                    url: url.into(),
+                    duration: Some(PLAYLIST_TRACK_DURATION),
+                },
+            )
+            .collect(),
+    }
+}
+
+/// A station created by [`rradio_messages::Command::PlayUrl`], which is treated as a live stream
+pub fn url(url: String) -> MockStation {
+    let url = ArcStr::from(url);
+
+    MockStation {
+        index: None,
+        title: url.clone(),
+        tracks: vec![MockTrack {
+            title: url.clone(),
+            artist: None,
+            album: None,
+            url,
+            duration: None,
+        }],
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+
+use anyhow::Context;
+
+use crate::local_storage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scheme {
+    Ws,
+    Wss,
+}
+
+impl Scheme {
+    fn from_page_protocol(protocol: &str) -> Self {
+        if protocol == "https:" {
+            Self::Wss
+        } else {
+            Self::Ws
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scheme::Ws => "ws",
+            Scheme::Wss => "wss",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseApiEndpointError {
+    #[error("Unsupported scheme {0:?}, expected ws, wss, http or https")]
+    UnsupportedScheme(String),
+    #[error("No host")]
+    NoHost,
+    #[error("Invalid host {0:?}")]
+    InvalidHost(String),
+    #[error("Invalid port {0:?}")]
+    InvalidPort(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ApiEndpoint {
+    pub scheme: Scheme,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+}
+
+impl ApiEndpoint {
+    pub const SERVER_STORAGE_KEY: &'static str = "RRADIO_SERVER";
+    pub const URL_STORAGE_KEY: &'static str = "RRADIO_API_URL";
+
+    const DEFAULT_PATH: &'static str = "/api";
+
+    /// Parse an endpoint, which may be a bare `host[:port]` or a full URL.
+    /// `http` and `https` are accepted as aliases for `ws` and `wss`.
+    /// A missing path, or a bare `/`, is the default path `/api`.
+    pub fn parse(value: &str, default_scheme: Scheme) -> Result<Self, ParseApiEndpointError> {
+        let value = value.trim();
+
+        let (scheme, rest) = match value.split_once("://") {
+            Some((scheme, rest)) => (
+                match scheme.to_ascii_lowercase().as_str() {
+                    "ws" | "http" => Scheme::Ws,
+                    "wss" | "https" => Scheme::Wss,
+                    _ => return Err(ParseApiEndpointError::UnsupportedScheme(scheme.into())),
+                },
+                rest,
+            ),
+            None => (default_scheme, value),
+        };
+
+        let (authority, path) = match rest.find('/') {
+            Some(path_start) => match rest.split_at(path_start) {
+                (authority, "/") => (authority, Self::DEFAULT_PATH),
+                authority_and_path => authority_and_path,
+            },
+            None => (rest, Self::DEFAULT_PATH),
+        };
+
+        let (host, port) = if let Some(ipv6_host) = authority.strip_prefix('[') {
+            let (host, rest) = ipv6_host
+                .split_once(']')
+                .ok_or_else(|| ParseApiEndpointError::InvalidHost(authority.into()))?;
+
+            let port = match rest {
+                "" => None,
+                _ => Some(
+                    rest.strip_prefix(':')
+                        .ok_or_else(|| ParseApiEndpointError::InvalidHost(authority.into()))?,
+                ),
+            };
+
+            (format!("[{host}]"), port)
+        } else {
+            match authority.split_once(':') {
+                Some((host, port)) => (host.into(), Some(port)),
+                None => (authority.into(), None),
+            }
+        };
+
+        if host.is_empty() || host == "[]" {
+            return Err(ParseApiEndpointError::NoHost);
+        }
+
+        if host
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '@' | '?' | '#' | '\\'))
+        {
+            return Err(ParseApiEndpointError::InvalidHost(host));
+        }
+
+        let port = port
+            .map(|port| {
+                port.parse()
+                    .map_err(|_| ParseApiEndpointError::InvalidPort(port.into()))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            scheme,
+            host,
+            port,
+            path: path.into(),
+        })
+    }
+
+    pub fn default_scheme() -> anyhow::Result<Scheme> {
+        Ok(Scheme::from_page_protocol(
+            &web_sys::window()
+                .context("No Window!")?
+                .location()
+                .protocol()
+                .map_err(|err| anyhow::anyhow!("No protocol: {:?}", err))?,
+        ))
+    }
+
+    /// The server address as configured by the user, if any
+    pub fn stored_address() -> Option<String> {
+        [Self::URL_STORAGE_KEY, Self::SERVER_STORAGE_KEY]
+            .iter()
+            .find_map(|key| local_storage::load(key))
+    }
+
+    /// Save the server address, or revert to the host which served the page if `address` is `None`
+    pub fn save_address(address: Option<&str>) -> anyhow::Result<()> {
+        local_storage::save(Self::URL_STORAGE_KEY, None)?;
+        local_storage::save(Self::SERVER_STORAGE_KEY, address)
+    }
+
+    /// The endpoint on the host which served the page
+    pub fn from_page() -> anyhow::Result<Self> {
+        let host = web_sys::window()
+            .context("No Window!")?
+            .location()
+            .host()
+            .map_err(|err| anyhow::anyhow!("No hostname: {:?}", err))?;
+
+        Self::parse(&host, Self::default_scheme()?).context("Invalid page host")
+    }
+
+    /// Load the endpoint from local storage, falling back to the host which served the page
+    pub fn load() -> anyhow::Result<Self> {
+        let default_scheme = Self::default_scheme()?;
+
+        if let Some(url) = local_storage::load(Self::URL_STORAGE_KEY) {
+            return Self::parse(&url, default_scheme)
+                .with_context(|| format!("Invalid {} {url:?}", Self::URL_STORAGE_KEY));
+        }
+
+        match local_storage::load(Self::SERVER_STORAGE_KEY) {
+            Some(host) => Self::parse(&host, default_scheme)
+                .with_context(|| format!("Invalid {} {host:?}", Self::SERVER_STORAGE_KEY)),
+            None => Self::from_page(),
+        }
+    }
+}
+
+impl ApiEndpoint {
+    /// The root of the web server hosting the endpoint, used to measure latency
+    pub fn probe_url(&self) -> String {
+        let scheme = match self.scheme {
+            Scheme::Ws => "http",
+            Scheme::Wss => "https",
+        };
+
+        match self.port {
+            Some(port) => format!("{scheme}://{}:{port}/", self.host),
+            None => format!("{scheme}://{}/", self.host),
+        }
+    }
+}
+
+impl fmt::Display for ApiEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            scheme,
+            host,
+            port,
+            path,
+        } = self;
+
+        write!(f, "{scheme}://{host}")?;
+
+        if let Some(port) = port {
+            write!(f, ":{port}")?;
+        }
+
+        write!(f, "{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Result<ApiEndpoint, ParseApiEndpointError> {
+        ApiEndpoint::parse(value, Scheme::Ws)
+    }
+
+    fn endpoint(scheme: Scheme, host: &str, port: Option<u16>, path: &str) -> ApiEndpoint {
+        ApiEndpoint {
+            scheme,
+            host: host.into(),
+            port,
+            path: path.into(),
+        }
+    }
+
+    #[test]
+    fn bare_host_uses_the_defaults() {
+        assert_eq!(
+            parse("rradio.local"),
+            Ok(endpoint(Scheme::Ws, "rradio.local", None, "/api"))
+        );
+        assert_eq!(
+            ApiEndpoint::parse(" rradio.local:8000 ", Scheme::Wss),
+            Ok(endpoint(Scheme::Wss, "rradio.local", Some(8000), "/api"))
+        );
+    }
+
+    #[test]
+    fn bare_trailing_slash_keeps_the_default_path() {
+        assert_eq!(
+            parse("rradio.local/"),
+            Ok(endpoint(Scheme::Ws, "rradio.local", None, "/api"))
+        );
+        assert_eq!(
+            parse("wss://example.com:8443/"),
+            Ok(endpoint(Scheme::Wss, "example.com", Some(8443), "/api"))
+        );
+    }
+
+    #[test]
+    fn full_url_keeps_its_path() {
+        assert_eq!(
+            parse("wss://example.com/rradio/api"),
+            Ok(endpoint(Scheme::Wss, "example.com", None, "/rradio/api"))
+        );
+        assert_eq!(
+            parse("ws://example.com:8000/rradio/"),
+            Ok(endpoint(Scheme::Ws, "example.com", Some(8000), "/rradio/"))
+        );
+    }
+
+    #[test]
+    fn http_schemes_are_aliases() {
+        assert_eq!(
+            parse("http://example.com").map(|endpoint| endpoint.scheme),
+            Ok(Scheme::Ws)
+        );
+        assert_eq!(
+            parse("HTTPS://example.com").map(|endpoint| endpoint.scheme),
+            Ok(Scheme::Wss)
+        );
+        assert_eq!(
+            ApiEndpoint::parse("ws://example.com", Scheme::Wss).map(|endpoint| endpoint.scheme),
+            Ok(Scheme::Ws)
+        );
+    }
+
+    #[test]
+    fn bracketed_ipv6_hosts() {
+        assert_eq!(
+            parse("[::1]"),
+            Ok(endpoint(Scheme::Ws, "[::1]", None, "/api"))
+        );
+        assert_eq!(
+            parse("wss://[fe80::1]:8000/api"),
+            Ok(endpoint(Scheme::Wss, "[fe80::1]", Some(8000), "/api"))
+        );
+        assert_eq!(
+            parse("[::1"),
+            Err(ParseApiEndpointError::InvalidHost("[::1".into()))
+        );
+        assert_eq!(
+            parse("[::1]8000"),
+            Err(ParseApiEndpointError::InvalidHost("[::1]8000".into()))
+        );
+    }
+
+    #[test]
+    fn invalid_endpoints_are_rejected() {
+        assert_eq!(
+            parse("ftp://example.com"),
+            Err(ParseApiEndpointError::UnsupportedScheme("ftp".into()))
+        );
+        assert_eq!(parse(""), Err(ParseApiEndpointError::NoHost));
+        assert_eq!(parse(":8000"), Err(ParseApiEndpointError::NoHost));
+        assert_eq!(parse("ws://[]:8000"), Err(ParseApiEndpointError::NoHost));
+        assert_eq!(
+            parse("user@example.com"),
+            Err(ParseApiEndpointError::InvalidHost(
+                "user@example.com".into()
+            ))
+        );
+        assert_eq!(
+            parse("example.com:http"),
+            Err(ParseApiEndpointError::InvalidPort("http".into()))
+        );
+        assert_eq!(
+            parse("example.com:65536"),
+            Err(ParseApiEndpointError::InvalidPort("65536".into()))
+        );
+    }
+
+    #[test]
+    fn display_and_probe_url() {
+        let endpoint = endpoint(Scheme::Wss, "[::1]", Some(8443), "/rradio/api");
+
+        assert_eq!(endpoint.to_string(), "wss://[::1]:8443/rradio/api");
+        assert_eq!(endpoint.probe_url(), "https://[::1]:8443/");
+        assert_eq!(parse(&endpoint.to_string()), Ok(endpoint));
+    }
+}
@@ -0,0 +1,255 @@
+use std::{collections::VecDeque, time::Duration};
+
+use rradio_messages::Command;
+
+struct QueuedCommand {
+    command: Command,
+    queued_at: Duration,
+}
+
+/// Commands sent while connecting or disconnected, to be sent once the connection is established.
+///
+/// - `SetVolume` and `SetIsMuted` are coalesced, so only the latest value is sent
+/// - Consecutive seeks are coalesced, with relative seeks added to the pending seek,
+///   and dropped if they are older than `max_seek_age` when flushed
+/// - A `PlayPause` cancels out a pending `PlayPause`, so toggles are never sent unpaired
+pub struct CommandQueue {
+    commands: VecDeque<QueuedCommand>,
+    max_seek_age: Duration,
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10))
+    }
+}
+
+fn is_seek(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::SeekTo(_) | Command::SeekBackwards(_) | Command::SeekForwards(_)
+    )
+}
+
+/// A relative seek of `forwards - backwards`
+fn relative_seek(forwards: Duration, backwards: Duration) -> Command {
+    match forwards.checked_sub(backwards) {
+        Some(offset) => Command::SeekForwards(offset),
+        None => Command::SeekBackwards(backwards.saturating_sub(forwards)),
+    }
+}
+
+/// The single seek which has the effect of `pending` followed by `command`
+fn coalesce_seek(pending: Option<Command>, command: Command) -> Command {
+    match (pending, command) {
+        (Some(Command::SeekTo(position)), Command::SeekForwards(offset)) => {
+            Command::SeekTo(position + offset)
+        }
+        (Some(Command::SeekTo(position)), Command::SeekBackwards(offset)) => {
+            Command::SeekTo(position.saturating_sub(offset))
+        }
+        (Some(Command::SeekForwards(pending)), Command::SeekForwards(offset)) => {
+            Command::SeekForwards(pending + offset)
+        }
+        (Some(Command::SeekBackwards(pending)), Command::SeekBackwards(offset)) => {
+            Command::SeekBackwards(pending + offset)
+        }
+        (Some(Command::SeekForwards(forwards)), Command::SeekBackwards(backwards))
+        | (Some(Command::SeekBackwards(backwards)), Command::SeekForwards(forwards)) => {
+            relative_seek(forwards, backwards)
+        }
+        (_, command) => command,
+    }
+}
+
+impl CommandQueue {
+    pub fn new(max_seek_age: Duration) -> Self {
+        Self {
+            commands: VecDeque::new(),
+            max_seek_age,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    fn remove_where(&mut self, predicate: impl Fn(&Command) -> bool) -> bool {
+        let previous_len = self.commands.len();
+
+        self.commands
+            .retain(|queued_command| !predicate(&queued_command.command));
+
+        self.commands.len() != previous_len
+    }
+
+    /// Remove the pending seek if it's the last queued command, unless it's too old to be sent.
+    ///
+    /// Seeks queued before a different command aren't merged, as that command might change the track
+    fn take_seek(&mut self, now: Duration) -> Option<Command> {
+        if !is_seek(&self.commands.back()?.command) {
+            return None;
+        }
+
+        let QueuedCommand { command, queued_at } = self.commands.pop_back()?;
+
+        (now.saturating_sub(queued_at) <= self.max_seek_age).then_some(command)
+    }
+
+    pub fn push(&mut self, command: Command, now: Duration) {
+        let command = if is_seek(&command) {
+            coalesce_seek(self.take_seek(now), command)
+        } else {
+            command
+        };
+
+        match &command {
+            Command::SetVolume(_) => {
+                self.remove_where(|command| matches!(command, Command::SetVolume(_)));
+            }
+            Command::SetIsMuted(_) => {
+                self.remove_where(|command| matches!(command, Command::SetIsMuted(_)));
+            }
+            Command::PlayPause
+                if self.remove_where(|command| matches!(command, Command::PlayPause)) =>
+            {
+                return;
+            }
+            _ => (),
+        }
+
+        self.commands.push_back(QueuedCommand {
+            command,
+            queued_at: now,
+        });
+    }
+
+    /// The next command to send, skipping commands which are too old to be useful
+    pub fn pop(&mut self, now: Duration) -> Option<Command> {
+        while let Some(QueuedCommand { command, queued_at }) = self.commands.pop_front() {
+            if is_seek(&command) && now.saturating_sub(queued_at) > self.max_seek_age {
+                continue;
+            }
+
+            return Some(command);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: Duration = Duration::from_secs(1000);
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn drain(queue: &mut CommandQueue, now: Duration) -> Vec<Command> {
+        std::iter::from_fn(|| queue.pop(now)).collect()
+    }
+
+    #[test]
+    fn relative_seeks_are_summed() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+        queue.push(Command::SeekBackwards(secs(5)), NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW).as_slice(),
+            [Command::SeekForwards(offset)] if *offset == secs(15)
+        ));
+
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+        queue.push(Command::SeekBackwards(secs(30)), NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW).as_slice(),
+            [Command::SeekBackwards(offset)] if *offset == secs(20)
+        ));
+    }
+
+    #[test]
+    fn relative_seeks_adjust_pending_seek_to() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Command::SeekTo(secs(60)), NOW);
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+        queue.push(Command::SeekBackwards(secs(100)), NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW).as_slice(),
+            [Command::SeekTo(position)] if *position == Duration::ZERO
+        ));
+    }
+
+    #[test]
+    fn seek_to_replaces_relative_seeks() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+        queue.push(Command::SeekBackwards(secs(5)), NOW);
+        queue.push(Command::SeekTo(secs(30)), NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW).as_slice(),
+            [Command::SeekTo(position)] if *position == secs(30)
+        ));
+    }
+
+    #[test]
+    fn seeks_are_not_merged_across_other_commands() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Command::SeekTo(secs(60)), NOW);
+        queue.push(Command::NextItem, NOW);
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW).as_slice(),
+            [Command::SeekTo(position), Command::NextItem, Command::SeekForwards(offset)]
+                if *position == secs(60) && *offset == secs(10)
+        ));
+    }
+
+    #[test]
+    fn stale_seeks_are_dropped() {
+        let mut queue = CommandQueue::new(secs(10));
+
+        queue.push(Command::SeekForwards(secs(10)), NOW);
+        queue.push(Command::SeekForwards(secs(5)), NOW + secs(20));
+
+        assert!(matches!(
+            drain(&mut queue, NOW + secs(20)).as_slice(),
+            [Command::SeekForwards(offset)] if *offset == secs(5)
+        ));
+
+        queue.push(Command::SeekTo(secs(30)), NOW);
+        queue.push(Command::PlayPause, NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW + secs(20)).as_slice(),
+            [Command::PlayPause]
+        ));
+    }
+
+    #[test]
+    fn play_pause_toggles_cancel_out() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Command::PlayPause, NOW);
+        queue.push(Command::SetVolume(10), NOW);
+        queue.push(Command::PlayPause, NOW);
+        queue.push(Command::SetVolume(20), NOW);
+
+        assert!(matches!(
+            drain(&mut queue, NOW).as_slice(),
+            [Command::SetVolume(20)]
+        ));
+    }
+}
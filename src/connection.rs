@@ -0,0 +1,659 @@
+use std::time::Duration;
+
+use dioxus::{logger::tracing::debug, prelude::*};
+use futures_util::{FutureExt, SinkExt, Stream, StreamExt};
+
+use crate::{
+    api_endpoint::ApiEndpoint,
+    command_queue::CommandQueue,
+    error_history::ErrorHistory,
+    event_recorder::{EventRecorder, Replay},
+    link_monitor::{LinkMonitor, DEFAULT_SILENCE_TIMEOUT, SILENCE_TIMEOUT_STORAGE_KEY},
+    local_storage,
+    reconnect::{Backoff, BrowserClock, Clock, ReconnectPolicy},
+    server_notice::ServerNotice,
+    transport::{
+        channel_transport, IncompatibleServerError, ServerMessage, Transport, WebSocketTransport,
+    },
+    ConnectionState, PlayerState, UpdateFromDiff,
+};
+
+pub type RetryNow = Signal<Option<futures_util::future::AbortHandle>>;
+
+enum AppCommand {
+    Command(rradio_messages::Command),
+    Message(anyhow::Result<ServerMessage>),
+    CheckLink,
+    ConnectionClosed,
+}
+
+/// A connection to a single rradio server.
+/// The commands coroutine is provided as context to child components.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ServerConnection {
+    pub connection_state: Signal<ConnectionState>,
+    pub player_state: Signal<PlayerState>,
+    pub retry_now: RetryNow,
+    pub pending_commands: Signal<usize>,
+    /// When an event was last received from the server
+    pub last_updated: Signal<Option<Duration>>,
+    /// The most recently measured round trip time
+    pub latency: Signal<Option<Duration>>,
+    /// Notices from the server which haven't been dismissed
+    pub notices: Signal<Vec<ServerNotice>>,
+    /// The errors reported by the server during this session
+    pub errors: Signal<ErrorHistory>,
+    pub recorder: Signal<EventRecorder>,
+    /// If set, the recording is played back instead of connecting to the server
+    pub replay: Signal<Option<Replay>>,
+    pub commands: Coroutine<rradio_messages::Command>,
+}
+
+#[derive(Clone, Copy)]
+struct ConnectionSignals {
+    connection_state: Signal<ConnectionState>,
+    player_state: Signal<PlayerState>,
+    retry_now: RetryNow,
+    pending_commands: Signal<usize>,
+    last_updated: Signal<Option<Duration>>,
+    latency: Signal<Option<Duration>>,
+    notices: Signal<Vec<ServerNotice>>,
+    /// Kept when the connection is restarted, as it covers the whole session
+    errors: Signal<ErrorHistory>,
+}
+
+/// The first diff after (re)connecting contains the full state, so discard the previous state
+fn apply_diff(
+    player_state: &mut PlayerState,
+    diff: rradio_messages::PlayerStateDiff,
+    is_first_event: &mut bool,
+) {
+    if std::mem::take(is_first_event) {
+        player_state.resync(diff);
+    } else {
+        player_state.update_from_diff(diff);
+    }
+}
+
+impl ConnectionSignals {
+    /// Never show state from a previously connected server
+    fn reset(mut self) {
+        self.connection_state.set(ConnectionState::Connecting);
+        self.player_state.set(PlayerState::default());
+        self.retry_now.set(None);
+        self.pending_commands.set(0);
+        self.last_updated.set(None);
+        self.latency.set(None);
+        self.notices.set(Vec::new());
+    }
+
+    fn handle_event(mut self, event: rradio_messages::Event, is_first_event: &mut bool) {
+        self.last_updated.set(Some(BrowserClock.now()));
+
+        match event {
+            rradio_messages::Event::PlayerStateChanged(diff) => {
+                if let Some(Some(latest_error)) = &diff.latest_error {
+                    // The first diff may contain an old error, which isn't worth interrupting the user for
+                    self.errors.write().record(latest_error, !*is_first_event);
+                }
+
+                self.player_state.with_mut(|current_player_state| {
+                    apply_diff(current_player_state, diff, is_first_event);
+                });
+            }
+        }
+    }
+}
+
+/// Connect to the server given by `load_endpoint`, which is called again each time the connection is restarted
+pub fn use_server_connection(
+    load_endpoint: impl Fn() -> anyhow::Result<ApiEndpoint> + 'static,
+) -> ServerConnection {
+    let signals = ConnectionSignals {
+        connection_state: use_signal(|| ConnectionState::Connecting),
+        player_state: use_signal(PlayerState::default),
+        retry_now: use_signal(|| None),
+        pending_commands: use_signal(|| 0),
+        last_updated: use_signal(|| None),
+        latency: use_signal(|| None),
+        notices: use_signal(Vec::new),
+        errors: use_signal(ErrorHistory::default),
+    };
+
+    let recorder = use_signal(EventRecorder::load);
+    let replay = use_signal(|| None::<Replay>);
+
+    let commands = use_coroutine(move |commands| {
+        let replay = replay.peek().clone();
+        let endpoint = load_endpoint();
+
+        async move {
+            match replay {
+                Some(replay) => run_replay(replay, commands, signals).await,
+                None => run(endpoint, commands, signals, recorder).await,
+            }
+        }
+        .map(ConnectionState::handle_closed(signals.connection_state))
+    });
+
+    let ConnectionSignals {
+        connection_state,
+        player_state,
+        retry_now,
+        pending_commands,
+        last_updated,
+        latency,
+        notices,
+        errors,
+    } = signals;
+
+    ServerConnection {
+        connection_state,
+        player_state,
+        retry_now,
+        pending_commands,
+        last_updated,
+        latency,
+        notices,
+        errors,
+        recorder,
+        replay,
+        commands,
+    }
+}
+
+fn load_silence_timeout() -> Duration {
+    local_storage::load(SILENCE_TIMEOUT_STORAGE_KEY)
+        .and_then(|timeout| timeout.parse().ok())
+        .map_or(DEFAULT_SILENCE_TIMEOUT, Duration::from_secs)
+}
+
+const LATENCY_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Measure the round trip time to the server by requesting `probe_url`, until the connection is closed
+async fn measure_latency(
+    probe_url: &str,
+    mut latency: Signal<Option<Duration>>,
+) -> std::convert::Infallible {
+    loop {
+        let sent_at = BrowserClock.now();
+
+        // Any response will do, even an opaque one from another origin
+        let result = gloo_net::http::RequestBuilder::new(probe_url)
+            .method(gloo_net::http::Method::HEAD)
+            .mode(web_sys::RequestMode::NoCors)
+            .cache(web_sys::RequestCache::NoStore)
+            .send()
+            .await;
+
+        latency.set(match result {
+            Ok(_) => Some(BrowserClock.now().saturating_sub(sent_at)),
+            Err(err) => {
+                debug!("Failed to measure latency: {err}");
+                None
+            }
+        });
+
+        gloo_timers::future::sleep(LATENCY_PROBE_INTERVAL).await;
+    }
+}
+
+/// Send `commands` to the server and pass received events and notices to `handle_event` and `handle_notice`,
+/// until the server closes the connection.
+/// On each of `ticks`, check that the connection is still alive.
+pub async fn run_protocol<T: Transport, C: Clock>(
+    transport: T,
+    commands: &mut UnboundedReceiver<rradio_messages::Command>,
+    ticks: impl Stream<Item = ()>,
+    link_monitor: &mut LinkMonitor<C>,
+    mut handle_event: impl FnMut(rradio_messages::Event),
+    mut handle_notice: impl FnMut(ServerNotice),
+) -> anyhow::Result<()> {
+    let (mut transport_tx, transport_rx) = transport.split();
+
+    let app_commands = futures_util::stream::select(
+        futures_util::stream::select(
+            commands.map(AppCommand::Command),
+            ticks.map(|()| AppCommand::CheckLink),
+        ),
+        transport_rx
+            .map(AppCommand::Message)
+            .chain(futures_util::stream::once(futures_util::future::ready(
+                AppCommand::ConnectionClosed,
+            ))),
+    );
+
+    futures_util::pin_mut!(app_commands);
+
+    while let Some(app_command) = app_commands.next().await {
+        match app_command {
+            AppCommand::Command(rradio_command) => {
+                transport_tx.send(rradio_command).await?;
+            }
+            AppCommand::Message(message) => match message? {
+                ServerMessage::Event(rradio_event) => {
+                    link_monitor.event_received(&rradio_event);
+                    handle_event(rradio_event);
+                }
+                ServerMessage::Notice(notice) => handle_notice(notice),
+            },
+            AppCommand::CheckLink => link_monitor.check()?,
+            AppCommand::ConnectionClosed => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `future`, adding any commands sent in the meantime to `command_queue`, e.g. while connecting or waiting to reconnect
+async fn queue_commands_while<F: std::future::Future>(
+    future: F,
+    commands: &mut UnboundedReceiver<rradio_messages::Command>,
+    command_queue: &mut CommandQueue,
+    mut pending_commands: Signal<usize>,
+) -> F::Output {
+    let queue_commands = async {
+        while let Some(command) = commands.next().await {
+            command_queue.push(command, BrowserClock.now());
+            pending_commands.set(command_queue.len());
+        }
+
+        futures_util::future::pending::<std::convert::Infallible>().await
+    };
+
+    futures_util::pin_mut!(future, queue_commands);
+
+    match futures_util::future::select(future, queue_commands).await {
+        futures_util::future::Either::Left((output, _)) => output,
+        futures_util::future::Either::Right((never, _)) => match never {},
+    }
+}
+
+async fn run(
+    endpoint: anyhow::Result<ApiEndpoint>,
+    mut commands: UnboundedReceiver<rradio_messages::Command>,
+    signals: ConnectionSignals,
+    recorder: Signal<EventRecorder>,
+) -> anyhow::Result<()> {
+    signals.reset();
+
+    let ConnectionSignals {
+        mut connection_state,
+        mut retry_now,
+        mut pending_commands,
+        mut latency,
+        mut notices,
+        ..
+    } = signals;
+
+    let silence_timeout = load_silence_timeout();
+
+    let endpoint = endpoint?;
+    let api_url = endpoint.to_string();
+    let probe_url = endpoint.probe_url();
+
+    let mut backoff = Backoff::new(ReconnectPolicy::default(), BrowserClock);
+    let mut command_queue = CommandQueue::default();
+
+    loop {
+        let result = async {
+            let mut transport = queue_commands_while(
+                WebSocketTransport::connect(&api_url, recorder),
+                &mut commands,
+                &mut command_queue,
+                pending_commands,
+            )
+            .await?;
+
+            connection_state.set(ConnectionState::Connected);
+
+            // Commands stay queued until the transport is ready for them, so none are lost if the connection drops again
+            loop {
+                futures_util::future::poll_fn(|cx| transport.poll_ready_unpin(cx)).await?;
+
+                let Some(command) = command_queue.pop(BrowserClock.now()) else {
+                    break;
+                };
+
+                transport.start_send_unpin(command)?;
+                pending_commands.set(command_queue.len());
+            }
+
+            transport.flush().await?;
+            pending_commands.set(0);
+
+            let mut is_first_event = true;
+            let mut link_monitor = LinkMonitor::new(silence_timeout, BrowserClock);
+
+            let protocol = run_protocol(
+                transport,
+                &mut commands,
+                gloo_timers::future::IntervalStream::new(1000),
+                &mut link_monitor,
+                |event| {
+                    backoff.reset();
+                    signals.handle_event(event, &mut is_first_event);
+                },
+                |notice| notices.write().push(notice),
+            );
+
+            let probe = measure_latency(&probe_url, latency);
+
+            futures_util::pin_mut!(protocol, probe);
+
+            match futures_util::future::select(protocol, probe).await {
+                futures_util::future::Either::Left((result, _)) => result,
+                futures_util::future::Either::Right((never, _)) => match never {},
+            }
+        }
+        .await;
+
+        latency.set(None);
+
+        let err = match result {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        // Reconnecting won't help
+        if err.is::<IncompatibleServerError>() {
+            return Err(err);
+        }
+
+        let Some((retry_delay, retry_at)) = backoff.next_retry() else {
+            return Err(err);
+        };
+
+        connection_state.set(ConnectionState::WaitingToReconnect {
+            error: rradio_messages::arcstr::format!("{:#}", err),
+            retry_at,
+        });
+
+        // Wait and then try to reconnect, unless the user asks to retry sooner
+        let (wait, retry_now_handle) =
+            futures_util::future::abortable(gloo_timers::future::sleep(retry_delay));
+        retry_now.set(Some(retry_now_handle));
+
+        // The wait is aborted if the user retries now
+        let _ =
+            queue_commands_while(wait, &mut commands, &mut command_queue, pending_commands).await;
+
+        retry_now.set(None);
+        connection_state.set(ConnectionState::Connecting);
+    }
+}
+
+/// Play back a recording through an in-memory transport, without connecting to a server
+async fn run_replay(
+    replay: Replay,
+    mut commands: UnboundedReceiver<rradio_messages::Command>,
+    signals: ConnectionSignals,
+) -> anyhow::Result<()> {
+    signals.reset();
+
+    let ConnectionSignals {
+        mut connection_state,
+        mut notices,
+        ..
+    } = signals;
+
+    let (transport, fake_server) = channel_transport();
+
+    connection_state.set(ConnectionState::Connected);
+
+    let mut is_first_event = true;
+
+    let (result, ()) = futures_util::future::join(
+        run_protocol(
+            transport,
+            &mut commands,
+            futures_util::stream::pending(),
+            &mut LinkMonitor::new(Duration::MAX, BrowserClock),
+            |event| signals.handle_event(event, &mut is_first_event),
+            |notice| notices.write().push(notice),
+        ),
+        replay.play(fake_server),
+    )
+    .await;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use rradio_messages::{Command, Event, PipelineState};
+
+    use super::*;
+    use crate::{reconnect::FakeClock, tests::empty_diff, transport::FakeServer};
+
+    const SILENCE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    struct Outcome {
+        result: anyhow::Result<()>,
+        player_state: PlayerState,
+        notices: Vec<ServerNotice>,
+        commands_sent: Vec<Command>,
+    }
+
+    fn state_changed(diff: rradio_messages::PlayerStateDiff) -> ServerMessage {
+        ServerMessage::Event(Event::PlayerStateChanged(diff))
+    }
+
+    /// Run the protocol against a fake server which sends `messages`,
+    /// and then closes the connection once it has received `close_after` commands, or never if `None`
+    fn run_script(
+        player_state: PlayerState,
+        commands: Vec<Command>,
+        messages: Vec<anyhow::Result<ServerMessage>>,
+        close_after: Option<usize>,
+        ticks: impl Stream<Item = ()>,
+        clock: &FakeClock,
+    ) -> Outcome {
+        let (transport, fake_server) = channel_transport();
+        let FakeServer {
+            commands: server_commands,
+            events,
+        } = fake_server;
+
+        for message in messages {
+            events.unbounded_send(message).unwrap();
+        }
+
+        let (app_commands_tx, mut app_commands) = futures_channel::mpsc::unbounded();
+
+        for command in commands {
+            app_commands_tx.unbounded_send(command).unwrap();
+        }
+
+        let mut player_state = player_state;
+        let mut notices = Vec::new();
+        let mut is_first_event = true;
+        let mut link_monitor = LinkMonitor::new(SILENCE_TIMEOUT, clock);
+
+        let protocol = run_protocol(
+            transport,
+            &mut app_commands,
+            ticks,
+            &mut link_monitor,
+            |event| match event {
+                Event::PlayerStateChanged(diff) => {
+                    apply_diff(&mut player_state, diff, &mut is_first_event);
+                }
+            },
+            |notice| notices.push(notice),
+        );
+
+        let server = async move {
+            let commands_sent = server_commands
+                .take(close_after.unwrap_or(usize::MAX))
+                .collect::<Vec<_>>()
+                .await;
+
+            drop(events);
+
+            commands_sent
+        };
+
+        let (result, commands_sent) =
+            futures_executor::block_on(futures_util::future::join(protocol, server));
+
+        Outcome {
+            result,
+            player_state,
+            notices,
+            commands_sent,
+        }
+    }
+
+    #[test]
+    fn first_diff_replaces_state_and_later_diffs_update_it() {
+        let clock = FakeClock::new(0.0);
+
+        // State left over from the previous connection
+        let previous_state = PlayerState {
+            is_muted: true,
+            track_position: Some(Duration::from_secs(30)),
+            ..PlayerState::default()
+        };
+
+        let outcome = run_script(
+            previous_state,
+            Vec::new(),
+            vec![
+                Ok(state_changed(rradio_messages::PlayerStateDiff {
+                    pipeline_state: Some(PipelineState::Playing),
+                    volume: Some(50),
+                    ..empty_diff()
+                })),
+                Ok(state_changed(rradio_messages::PlayerStateDiff {
+                    volume: Some(60),
+                    buffering: Some(80),
+                    ..empty_diff()
+                })),
+            ],
+            Some(0),
+            futures_util::stream::pending(),
+            &clock,
+        );
+
+        outcome.result.unwrap();
+
+        let player_state = outcome.player_state;
+
+        assert_eq!(player_state.pipeline_state, PipelineState::Playing);
+        assert_eq!(player_state.volume, 60);
+        assert_eq!(player_state.buffering, 80);
+        assert!(!player_state.is_muted);
+        assert_eq!(player_state.track_position, None);
+    }
+
+    #[test]
+    fn commands_are_sent_in_order() {
+        let clock = FakeClock::new(0.0);
+
+        let outcome = run_script(
+            PlayerState::default(),
+            vec![
+                Command::PlayPause,
+                Command::SetVolume(40),
+                Command::NextItem,
+            ],
+            Vec::new(),
+            Some(3),
+            futures_util::stream::pending(),
+            &clock,
+        );
+
+        outcome.result.unwrap();
+
+        assert!(matches!(
+            outcome.commands_sent.as_slice(),
+            [
+                Command::PlayPause,
+                Command::SetVolume(40),
+                Command::NextItem
+            ]
+        ));
+    }
+
+    #[test]
+    fn notices_are_passed_on() {
+        let clock = FakeClock::new(0.0);
+
+        let notice = ServerNotice::Maintenance {
+            message: String::from("Restarting at 3am"),
+        };
+
+        let outcome = run_script(
+            PlayerState::default(),
+            Vec::new(),
+            vec![Ok(ServerMessage::Notice(notice.clone()))],
+            Some(0),
+            futures_util::stream::pending(),
+            &clock,
+        );
+
+        outcome.result.unwrap();
+
+        assert_eq!(outcome.notices, vec![notice]);
+        assert_eq!(
+            outcome.player_state.pipeline_state,
+            PipelineState::default()
+        );
+    }
+
+    #[test]
+    fn transport_errors_end_the_protocol() {
+        let clock = FakeClock::new(0.0);
+
+        let outcome = run_script(
+            PlayerState::default(),
+            Vec::new(),
+            vec![
+                Ok(state_changed(rradio_messages::PlayerStateDiff {
+                    volume: Some(70),
+                    ..empty_diff()
+                })),
+                Err(anyhow::anyhow!("Failed to decode Event")),
+                Ok(state_changed(rradio_messages::PlayerStateDiff {
+                    volume: Some(80),
+                    ..empty_diff()
+                })),
+            ],
+            None,
+            futures_util::stream::pending(),
+            &clock,
+        );
+
+        assert_eq!(
+            outcome.result.unwrap_err().to_string(),
+            "Failed to decode Event"
+        );
+        assert_eq!(outcome.player_state.volume, 70);
+    }
+
+    #[test]
+    fn silent_connection_is_closed_while_playing() {
+        let clock = FakeClock::new(0.0);
+
+        let outcome = run_script(
+            PlayerState::default(),
+            Vec::new(),
+            vec![Ok(state_changed(rradio_messages::PlayerStateDiff {
+                pipeline_state: Some(PipelineState::Playing),
+                ..empty_diff()
+            }))],
+            None,
+            futures_util::stream::repeat_with(|| {
+                clock.advance(SILENCE_TIMEOUT + Duration::from_secs(1));
+            }),
+            &clock,
+        );
+
+        assert!(outcome
+            .result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Connection lost"));
+    }
+}
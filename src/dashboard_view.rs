@@ -0,0 +1,134 @@
+use dioxus::prelude::*;
+
+use crate::{
+    api_endpoint::ApiEndpoint,
+    connection::{use_server_connection, ServerConnection},
+    handle_input,
+    servers_view::{SavedServers, Server},
+    ConnectionState, PlayerState,
+};
+
+fn station_title(player_state: &PlayerState) -> String {
+    match player_state.current_station.as_ref() {
+        rradio_messages::CurrentStation::NoStation => String::from("No Station"),
+        rradio_messages::CurrentStation::FailedToPlayStation { .. } => {
+            String::from("Failed to Play Station")
+        }
+        rradio_messages::CurrentStation::PlayingStation {
+            index,
+            source_type,
+            title,
+            ..
+        } => match (title, index) {
+            (Some(title), _) => title.to_string(),
+            (None, Some(index)) => format!("Station {index}"),
+            (None, None) => source_type.to_string(),
+        },
+    }
+}
+
+fn track_description(player_state: &PlayerState) -> String {
+    let tags = player_state.current_track_tags.as_ref().as_ref();
+
+    let track = match player_state.current_station.as_ref() {
+        rradio_messages::CurrentStation::PlayingStation {
+            tracks: Some(tracks),
+            ..
+        } => tracks.get(player_state.current_track_index),
+        _ => None,
+    };
+
+    let title = tags
+        .and_then(|tags| tags.title.as_deref())
+        .or_else(|| track.and_then(|track| track.title.as_deref()));
+    let artist = tags
+        .and_then(|tags| tags.artist.as_deref())
+        .or_else(|| track.and_then(|track| track.artist.as_deref()));
+
+    match (title, artist) {
+        (Some(title), Some(artist)) => format!("{title} - {artist}"),
+        (Some(title), None) => title.into(),
+        (None, Some(artist)) => artist.into(),
+        (None, None) => String::new(),
+    }
+}
+
+#[component]
+fn RoomView(server: Server) -> Element {
+    let address = server.address.clone();
+
+    let ServerConnection {
+        connection_state,
+        player_state,
+        commands,
+        ..
+    } = use_server_connection(move || {
+        Ok(ApiEndpoint::parse(
+            &address,
+            ApiEndpoint::default_scheme()?,
+        )?)
+    });
+
+    let volume_min = rradio_messages::VOLUME_MIN;
+    let volume_max = rradio_messages::VOLUME_ZERO_DB;
+
+    let status = match &*connection_state.read() {
+        ConnectionState::Connected => None,
+        ConnectionState::Connecting => Some(String::from("Connecting...")),
+        ConnectionState::Disconnected => Some(String::from("RRadio has terminated")),
+        ConnectionState::ConnectionError(err)
+        | ConnectionState::WaitingToReconnect { error: err, .. } => Some(err.to_string()),
+        ConnectionState::IncompatibleServer { .. } => Some(String::from("Incompatible server")),
+    };
+
+    let player_state = player_state.read();
+
+    let station_title = station_title(&player_state);
+    let track_description = track_description(&player_state);
+
+    rsx! {
+        fieldset {
+            class: "room",
+            legend { "{server.name}" }
+            if let Some(status) = status {
+                output { class: "room-status", "{status}" }
+            }
+            div { class: "room-station", "{station_title}" }
+            div { class: "room-track", "{track_description}" }
+            div {
+                class: "room-controls",
+                button { onclick: move |_| commands.send(rradio_messages::Command::PlayPause), "⏯️" }
+                "🔉"
+                input {
+                    "type": "range",
+                    min: "{volume_min}",
+                    max: "{volume_max}",
+                    value: "{player_state.volume}",
+                    oninput: move |ev| handle_input(rradio_messages::Command::SetVolume, &ev.value(), &commands)
+                }
+                "🔊"
+            }
+        }
+    }
+}
+
+#[component]
+pub fn DashboardView() -> Element {
+    let saved_servers = use_context::<SavedServers>();
+
+    let servers = saved_servers.servers.read();
+
+    if servers.is_empty() {
+        return rsx! {
+            p { "No saved servers. Add servers on the Settings page to see them here." }
+        };
+    }
+
+    let rooms = servers.iter().cloned().map(|server| {
+        rsx! { RoomView { key: "{server.name}:{server.address}", server } }
+    });
+
+    rsx! {
+        main { {rooms} }
+    }
+}
@@ -1,8 +1,9 @@
-use dioxus::prelude::*;
+use dioxus::{logger::tracing::error, prelude::*};
 
 use rradio_messages::{CurrentStation, Track, TrackTags};
+use web_sys::wasm_bindgen::JsCast;
 
-use crate::ConnectionState;
+use crate::{connection::ServerConnection, event_recorder::Replay, ConnectionState};
 
 use super::{FastEqRc, PlayerState};
 
@@ -95,10 +96,176 @@ fn CurrentStationView(
     }
 }
 
+/// Save `contents` as a file by following a download link to a blob URL.
+/// A data URL could exceed the browser's length limit for a full recording.
+fn download(file_name: &str, contents: &str) -> anyhow::Result<()> {
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/json");
+
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(
+        &web_sys::js_sys::Array::of1(&contents.into()),
+        &options,
+    )
+    .map_err(|err| anyhow::anyhow!("Failed to create blob: {err:?}"))?;
+
+    let url = web_sys::Url::create_object_url_with_blob(&blob)
+        .map_err(|err| anyhow::anyhow!("Failed to create blob URL: {err:?}"))?;
+
+    let link = gloo_utils::document()
+        .create_element("a")
+        .map_err(|err| anyhow::anyhow!("Failed to create link: {err:?}"))?
+        .dyn_into::<web_sys::HtmlElement>()
+        .map_err(|_| anyhow::anyhow!("Link is not an HTML element"))?;
+
+    link.set_attribute("href", &url)
+        .map_err(|err| anyhow::anyhow!("Failed to set link href: {err:?}"))?;
+
+    link.set_attribute("download", file_name)
+        .map_err(|err| anyhow::anyhow!("Failed to set link download: {err:?}"))?;
+
+    link.click();
+
+    // Release the blob once the download has started
+    gloo_timers::callback::Timeout::new(0, move || {
+        if let Err(err) = web_sys::Url::revoke_object_url(&url) {
+            error!("Failed to revoke blob URL: {err:?}");
+        }
+    })
+    .forget();
+
+    Ok(())
+}
+
+#[component]
+fn EventRecorderView(connection: ServerConnection) -> Element {
+    let ServerConnection {
+        mut recorder,
+        mut replay,
+        mut commands,
+        ..
+    } = connection;
+
+    let mut replay_speed = use_signal(|| 1_u32);
+    let mut replay_error = use_signal(|| None::<String>);
+
+    let is_enabled = recorder.read().is_enabled();
+    let recorded_count = recorder.read().len();
+    let is_replaying = replay.read().is_some();
+
+    let export = move |_| {
+        if let Err(err) = recorder
+            .peek()
+            .export()
+            .and_then(|recording| download("rradio-events.json", &recording))
+        {
+            error!("Failed to export events: {err:#}");
+        }
+    };
+
+    let load_replay = move |ev: Event<FormData>| async move {
+        let Some(files) = ev.files() else {
+            return;
+        };
+
+        let Some(file_name) = files.files().into_iter().next() else {
+            return;
+        };
+
+        let Some(recording) = files.read_file_to_string(&file_name).await else {
+            replay_error.set(Some(format!("Failed to read {file_name}")));
+            return;
+        };
+
+        match Replay::parse(&recording, f64::from(replay_speed())) {
+            Ok(new_replay) => {
+                replay_error.set(None);
+                replay.set(Some(new_replay));
+                commands.restart();
+            }
+            Err(err) => replay_error.set(Some(format!("Failed to parse {file_name}: {err:#}"))),
+        }
+    };
+
+    let speed_options = [1, 2, 5, 10].iter().copied().map(|speed| {
+        let is_selected = replay_speed() == speed;
+        rsx! {
+            option {
+                key: "{speed}",
+                selected: "{is_selected}",
+                value: "{speed}",
+                "{speed}×"
+            }
+        }
+    });
+
+    rsx! {
+        fieldset {
+            id: "event-recorder",
+            legend { "Event Recorder" }
+            label {
+                input {
+                    "type": "checkbox",
+                    checked: is_enabled,
+                    oninput: move |ev| recorder.write().set_is_enabled(ev.checked()),
+                }
+                "Record events and commands"
+            }
+            output { " {recorded_count} recorded " }
+            button {
+                "type": "button",
+                disabled: recorded_count == 0,
+                onclick: export,
+                "Export"
+            }
+            button {
+                "type": "button",
+                disabled: recorded_count == 0,
+                onclick: move |_| recorder.write().clear(),
+                "Clear"
+            }
+        }
+        fieldset {
+            id: "event-replay",
+            legend { "Replay" }
+            label {
+                "Speed: "
+                select {
+                    disabled: is_replaying,
+                    oninput: move |ev| {
+                        if let Ok(speed) = ev.value().parse() {
+                            replay_speed.set(speed);
+                        }
+                    },
+                    {speed_options}
+                }
+            }
+            if is_replaying {
+                button {
+                    "type": "button",
+                    onclick: move |_| {
+                        replay.set(None);
+                        commands.restart();
+                    },
+                    "Stop Replay"
+                }
+            } else {
+                input {
+                    "type": "file",
+                    accept: ".json,application/json",
+                    onchange: load_replay,
+                }
+            }
+            if let Some(err) = replay_error() {
+                output { "{err}" }
+            }
+        }
+    }
+}
+
 #[component]
-pub fn DebugView(connection_state: Signal<ConnectionState>, player_state: PlayerState) -> Element {
-    if let ConnectionState::Connecting = connection_state() {
-        return rsx! {};
+pub fn DebugView(connection: ServerConnection, player_state: Signal<PlayerState>) -> Element {
+    if let ConnectionState::Connecting = (connection.connection_state)() {
+        return rsx! { EventRecorderView { connection } };
     }
 
     let PlayerState {
@@ -114,9 +281,10 @@ pub fn DebugView(connection_state: Signal<ConnectionState>, player_state: Player
         track_position,
         ping_times,
         latest_error,
-    } = player_state;
+    } = player_state();
 
     rsx! {
+        EventRecorderView { connection }
         dl {
             dt { "Pipeline State: {pipeline_state:?}" }
             dt { "Pause Before Playing: {pause_before_playing:?}" }
@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+
+use dioxus::prelude::*;
+
+use rradio_messages::LatestError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorHistoryEntry {
+    pub error: LatestError,
+    /// Whether the error is still shown as a toast
+    pub is_toast: bool,
+}
+
+/// The errors reported by the server during this session, oldest first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorHistory {
+    entries: VecDeque<ErrorHistoryEntry>,
+    /// Errors which have been cleared, so that they aren't recorded again when resent after reconnecting
+    cleared: VecDeque<LatestError>,
+}
+
+impl ErrorHistory {
+    const CAPACITY: usize = 100;
+
+    /// Record `error`, unless it has already been recorded or cleared, e.g. when resent after reconnecting
+    pub fn record(&mut self, error: &LatestError, show_toast: bool) {
+        if self.entries.iter().any(|entry| entry.error == *error) || self.cleared.contains(error) {
+            return;
+        }
+
+        if self.entries.len() >= Self::CAPACITY {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(ErrorHistoryEntry {
+            error: error.clone(),
+            is_toast: show_toast,
+        });
+    }
+
+    pub fn entries(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &ErrorHistoryEntry> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn dismiss_toast(&mut self, index: usize) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.is_toast = false;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cleared
+            .extend(self.entries.drain(..).map(|entry| entry.error));
+
+        if let Some(excess) = self.cleared.len().checked_sub(Self::CAPACITY) {
+            self.cleared.drain(..excess);
+        }
+    }
+}
+
+fn format_timestamp(error: &LatestError) -> String {
+    error.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+#[component]
+pub fn ErrorToastsView(errors: Signal<ErrorHistory>) -> Element {
+    let history = errors.read();
+
+    let toasts = history
+        .entries()
+        .enumerate()
+        .filter(|(_, entry)| entry.is_toast)
+        .map(|(index, entry)| {
+            let error = &entry.error.error;
+            let timestamp = format_timestamp(&entry.error);
+
+            rsx! {
+                li {
+                    key: "{index}",
+                    class: "error",
+                    title: "{timestamp}",
+                    strong { "RRadio error: " }
+                    "{error}"
+                    button {
+                        "type": "button",
+                        title: "Dismiss",
+                        onclick: move |_| errors.write().dismiss_toast(index),
+                        "✖"
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if toasts.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        ul {
+            id: "error-toasts",
+            {toasts.into_iter()}
+        }
+    }
+}
+
+#[component]
+pub fn ErrorHistoryView(errors: Signal<ErrorHistory>) -> Element {
+    let history = errors.read();
+
+    let entries = history.entries().enumerate().rev().map(|(index, entry)| {
+        let error = &entry.error.error;
+        let timestamp = format_timestamp(&entry.error);
+
+        rsx! {
+            li {
+                key: "{index}",
+                time { "{timestamp}" }
+                " {error}"
+            }
+        }
+    });
+
+    rsx! {
+        main {
+            h2 { "Errors" }
+            if history.is_empty() {
+                p { "No errors have been reported by the server" }
+            } else {
+                ul {
+                    id: "error-history",
+                    {entries}
+                }
+                button {
+                    "type": "button",
+                    onclick: move |_| errors.write().clear(),
+                    "Clear"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rradio_messages::Error;
+
+    use super::*;
+
+    /// `secs` after the Unix epoch. chrono isn't a direct dependency, so its types can't be named.
+    #[allow(clippy::default_trait_access)]
+    fn error_at(secs: u64, error: Error) -> LatestError {
+        let mut latest_error = LatestError {
+            timestamp: Default::default(),
+            error,
+        };
+
+        latest_error.timestamp += Duration::from_secs(secs);
+
+        latest_error
+    }
+
+    fn toasts(history: &ErrorHistory) -> Vec<bool> {
+        history.entries().map(|entry| entry.is_toast).collect()
+    }
+
+    #[test]
+    fn duplicate_errors_are_recorded_once() {
+        let mut history = ErrorHistory::default();
+
+        history.record(&error_at(1, Error::NoPlaylist), true);
+        history.record(&error_at(1, Error::NoPlaylist), true);
+
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn distinct_errors_are_all_recorded() {
+        let mut history = ErrorHistory::default();
+
+        history.record(&error_at(1, Error::NoPlaylist), true);
+        history.record(&error_at(2, Error::NoPlaylist), true);
+        history.record(&error_at(2, Error::InvalidTrackIndex(3)), true);
+
+        let errors = history
+            .entries()
+            .map(|entry| entry.error.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            [
+                error_at(1, Error::NoPlaylist),
+                error_at(2, Error::NoPlaylist),
+                error_at(2, Error::InvalidTrackIndex(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_new_errors_are_shown_as_toasts() {
+        let mut history = ErrorHistory::default();
+
+        // e.g. the latest error sent when connecting
+        history.record(&error_at(1, Error::NoPlaylist), false);
+        history.record(&error_at(2, Error::NoPlaylist), true);
+        assert_eq!(toasts(&history), [false, true]);
+
+        // Resending an error doesn't show a toast again
+        history.record(&error_at(1, Error::NoPlaylist), true);
+        history.dismiss_toast(1);
+        history.record(&error_at(2, Error::NoPlaylist), true);
+        assert_eq!(toasts(&history), [false, false]);
+    }
+
+    #[test]
+    fn cleared_errors_are_not_recorded_again() {
+        let mut history = ErrorHistory::default();
+
+        history.record(&error_at(1, Error::NoPlaylist), true);
+        history.clear();
+        assert!(history.is_empty());
+
+        history.record(&error_at(1, Error::NoPlaylist), true);
+        assert!(history.is_empty());
+
+        history.record(&error_at(2, Error::NoPlaylist), true);
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn oldest_errors_are_forgotten() {
+        let mut history = ErrorHistory::default();
+
+        for secs in 0..=ErrorHistory::CAPACITY as u64 {
+            history.record(&error_at(secs, Error::NoPlaylist), true);
+        }
+
+        assert_eq!(history.len(), ErrorHistory::CAPACITY);
+        assert_eq!(
+            history.entries().next().map(|entry| &entry.error),
+            Some(&error_at(1, Error::NoPlaylist))
+        );
+    }
+}
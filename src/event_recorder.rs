@@ -0,0 +1,177 @@
+use std::{collections::VecDeque, rc::Rc, time::Duration};
+
+use anyhow::Context;
+use dioxus::{
+    logger::tracing::{error, info},
+    prelude::*,
+};
+use futures_util::StreamExt;
+
+use crate::{
+    local_storage,
+    reconnect::{BrowserClock, Clock},
+    transport::{FakeServer, ServerMessage},
+};
+
+/// A message as encoded by the rradio protocol
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum RecordedMessage {
+    Command(Vec<u8>),
+    Event(Vec<u8>),
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecordedEntry {
+    pub timestamp: Duration,
+    pub message: RecordedMessage,
+}
+
+/// The most recent commands sent and events received, kept to be attached to bug reports
+pub struct EventRecorder {
+    is_enabled: bool,
+    entries: VecDeque<RecordedEntry>,
+}
+
+impl EventRecorder {
+    pub const STORAGE_KEY: &'static str = "RRADIO_RECORD_EVENTS";
+    const CAPACITY: usize = 1000;
+
+    pub fn load() -> Self {
+        Self {
+            is_enabled: local_storage::load_flag(Self::STORAGE_KEY),
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    pub fn set_is_enabled(&mut self, is_enabled: bool) {
+        self.is_enabled = is_enabled;
+
+        if let Err(err) = local_storage::save_flag(Self::STORAGE_KEY, is_enabled) {
+            error!("{err:#}");
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn record(&mut self, message: RecordedMessage) {
+        if self.entries.len() == Self::CAPACITY {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(RecordedEntry {
+            timestamp: BrowserClock.now(),
+            message,
+        });
+    }
+
+    pub fn export(&self) -> anyhow::Result<String> {
+        serde_json::to_string(&self.entries).map_err(anyhow::Error::from)
+    }
+}
+
+/// Record the message created by `message`, if recording is enabled.
+/// The recorder isn't written to otherwise, so views showing it aren't rerendered for each message.
+pub fn record(mut recorder: Signal<EventRecorder>, message: impl FnOnce() -> RecordedMessage) {
+    if recorder.peek().is_enabled() {
+        recorder.write().record(message());
+    }
+}
+
+/// A recording to be played back instead of connecting to a server
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub recording: Rc<[RecordedEntry]>,
+    pub speed: f64,
+}
+
+impl Replay {
+    /// `speed` must be positive, as the gaps between events are divided by it
+    pub fn parse(recording: &str, speed: f64) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            speed.is_finite() && speed > 0.0,
+            "Invalid replay speed {speed}"
+        );
+
+        Ok(Self {
+            recording: serde_json::from_str::<Vec<RecordedEntry>>(recording)?.into(),
+            speed,
+        })
+    }
+
+    /// Send the recorded events to the client, preserving the gaps between them, scaled by `speed`.
+    /// Commands sent by the client are ignored.
+    pub async fn play(self, server: FakeServer) {
+        let FakeServer { commands, events } = server;
+
+        let play_events = async move {
+            let mut previous_timestamp = None;
+
+            for RecordedEntry { timestamp, message } in self.recording.iter().cloned() {
+                let RecordedMessage::Event(mut buffer) = message else {
+                    continue;
+                };
+
+                if let Some(previous_timestamp) = previous_timestamp.replace(timestamp) {
+                    gloo_timers::future::sleep(
+                        timestamp
+                            .saturating_sub(previous_timestamp)
+                            .div_f64(self.speed),
+                    )
+                    .await;
+                }
+
+                let event = rradio_messages::Event::decode(&mut buffer)
+                    .context("Failed to decode recorded Event")
+                    .map(ServerMessage::Event);
+
+                if events.unbounded_send(event).is_err() {
+                    return;
+                }
+            }
+        };
+
+        let ignore_commands = commands.for_each(|_| {
+            info!("Ignoring command during replay");
+            futures_util::future::ready(())
+        });
+
+        futures_util::pin_mut!(play_events);
+
+        futures_util::future::select(play_events, ignore_commands).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDING: &str = r#"[{"timestamp":{"secs":1,"nanos":0},"message":{"Event":[0]}}]"#;
+
+    #[test]
+    fn parses_recording() {
+        let replay = Replay::parse(RECORDING, 2.0).unwrap();
+
+        assert_eq!(replay.recording.len(), 1);
+        assert_eq!(replay.speed, 2.0);
+    }
+
+    #[test]
+    fn rejects_invalid_speeds() {
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                Replay::parse(RECORDING, speed).unwrap_err().to_string(),
+                format!("Invalid replay speed {speed}")
+            );
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::{collections::BTreeMap, rc::Rc, time::Duration};
+
+use dioxus::{logger::tracing::error, prelude::*};
+use gloo_storage::Storage;
+use web_sys::wasm_bindgen::{closure::Closure, JsCast};
+
+use crate::{local_storage, PlayerState};
+
+pub const STORAGE_KEY: &str = "RRADIO_KEY_BINDINGS";
+
+const SEEK_OFFSET: Duration = Duration::from_secs(10);
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum Action {
+    PlayPause,
+    SeekBackwards,
+    SeekForwards,
+    PreviousItem,
+    NextItem,
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    ShowHelp,
+}
+
+impl Action {
+    const ALL: [Self; 9] = [
+        Self::PlayPause,
+        Self::SeekBackwards,
+        Self::SeekForwards,
+        Self::PreviousItem,
+        Self::NextItem,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::ToggleMute,
+        Self::ShowHelp,
+    ];
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::PlayPause => "Play/Pause",
+            Self::SeekBackwards => "Seek backwards 10s",
+            Self::SeekForwards => "Seek forwards 10s",
+            Self::PreviousItem => "Previous track",
+            Self::NextItem => "Next track",
+            Self::VolumeUp => "Volume up",
+            Self::VolumeDown => "Volume down",
+            Self::ToggleMute => "Mute/Unmute",
+            Self::ShowHelp => "Show/Hide keyboard shortcuts",
+        }
+    }
+
+    /// Keys are [`KeyboardEvent.key`](https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/key) values
+    fn default_key(self) -> &'static str {
+        match self {
+            Self::PlayPause => " ",
+            Self::SeekBackwards => "ArrowLeft",
+            Self::SeekForwards => "ArrowRight",
+            Self::PreviousItem => "ArrowUp",
+            Self::NextItem => "ArrowDown",
+            Self::VolumeUp => "+",
+            Self::VolumeDown => "-",
+            Self::ToggleMute => "m",
+            Self::ShowHelp => "?",
+        }
+    }
+
+    /// `None` if the action doesn't affect the server
+    fn command(self, player_state: &PlayerState) -> Option<rradio_messages::Command> {
+        use rradio_messages::Command;
+
+        Some(match self {
+            Self::PlayPause => Command::PlayPause,
+            Self::SeekBackwards => Command::SeekBackwards(SEEK_OFFSET),
+            Self::SeekForwards => Command::SeekForwards(SEEK_OFFSET),
+            Self::PreviousItem => Command::SmartPreviousItem,
+            Self::NextItem => Command::NextItem,
+            Self::VolumeUp => Command::VolumeUp,
+            Self::VolumeDown => Command::VolumeDown,
+            Self::ToggleMute => Command::SetIsMuted(!player_state.is_muted),
+            Self::ShowHelp => return None,
+        })
+    }
+}
+
+/// Keys which are only pressed together with the key which is being bound, e.g. Shift to type "?"
+fn is_modifier_key(key: &str) -> bool {
+    matches!(
+        key,
+        "Shift" | "Control" | "Alt" | "AltGraph" | "Meta" | "CapsLock"
+    )
+}
+
+/// "1" is the first track and "0" is the tenth
+fn track_index(key: &str) -> Option<usize> {
+    key.parse::<usize>()
+        .ok()
+        .filter(|_| key.len() == 1)
+        .map(|digit| (digit + 9) % 10)
+}
+
+fn display_key(key: &str) -> &str {
+    match key {
+        " " => "Space",
+        key => key,
+    }
+}
+
+/// The key bound to each action. Number keys always select a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    keys: BTreeMap<Action, String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL
+                .iter()
+                .map(|&action| (action, String::from(action.default_key())))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Actions without a saved binding use the default key
+    fn with_saved_keys(saved_keys: BTreeMap<Action, String>) -> Self {
+        let mut bindings = Self::default();
+        bindings.keys.extend(saved_keys);
+        bindings
+    }
+
+    pub fn load() -> Self {
+        match gloo_storage::LocalStorage::get::<BTreeMap<Action, String>>(STORAGE_KEY) {
+            Ok(saved_keys) => Self::with_saved_keys(saved_keys),
+            Err(gloo_storage::errors::StorageError::KeyNotFound(_)) => Self::default(),
+            Err(err) => {
+                error!("Failed to load {STORAGE_KEY}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Err(err) = gloo_storage::LocalStorage::set(STORAGE_KEY, &self.keys) {
+            error!("Failed to save {STORAGE_KEY}: {err}");
+        }
+    }
+
+    fn key(&self, action: Action) -> &str {
+        self.keys.get(&action).map_or("", String::as_str)
+    }
+
+    fn action(&self, key: &str) -> Option<Action> {
+        self.keys
+            .iter()
+            .find_map(|(&action, action_key)| (action_key == key).then_some(action))
+    }
+
+    /// If another action was bound to `key`, it takes the previous key of `action`
+    fn bind(&mut self, action: Action, key: &str) {
+        let previous_key = self
+            .keys
+            .insert(action, String::from(key))
+            .unwrap_or_default();
+
+        if let Some(other_action) = self.keys.iter().find_map(|(&other_action, other_key)| {
+            (other_action != action && *other_key == key).then_some(other_action)
+        }) {
+            self.keys.insert(other_action, previous_key);
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+
+        if let Err(err) = local_storage::save(STORAGE_KEY, None) {
+            error!("{err:#}");
+        }
+    }
+}
+
+/// Shortcuts are ignored while the user is typing, or when space would press a focused button
+fn is_focus_in_control(event: &web_sys::KeyboardEvent) -> bool {
+    let Some(target) = event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+    else {
+        return false;
+    };
+
+    match target.tag_name().as_str() {
+        "INPUT" | "TEXTAREA" | "SELECT" => true,
+        "BUTTON" | "A" => matches!(event.key().as_str(), " " | "Enter"),
+        _ => target.is_content_editable(),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyboardShortcuts {
+    bindings: Signal<KeyBindings>,
+    is_help_shown: Signal<bool>,
+    /// The action which will be bound to the next key pressed
+    rebinding: Signal<Option<Action>>,
+}
+
+impl KeyboardShortcuts {
+    fn handle_keydown(
+        mut self,
+        event: &web_sys::KeyboardEvent,
+        player_state: Signal<PlayerState>,
+        commands: Coroutine<rradio_messages::Command>,
+    ) {
+        if event.default_prevented() || event.ctrl_key() || event.alt_key() || event.meta_key() {
+            return;
+        }
+
+        let key = event.key();
+
+        let rebinding = *self.rebinding.peek();
+
+        if let Some(action) = rebinding {
+            // Wait for the key which is being modified
+            if is_modifier_key(&key) {
+                return;
+            }
+
+            event.prevent_default();
+            self.rebinding.set(None);
+
+            if key != "Escape" {
+                let mut bindings = self.bindings.write();
+                bindings.bind(action, &key);
+                bindings.save();
+            }
+
+            return;
+        }
+
+        if is_focus_in_control(event) {
+            return;
+        }
+
+        if key == "Escape" && *self.is_help_shown.peek() {
+            self.is_help_shown.set(false);
+            return;
+        }
+
+        let action = self.bindings.peek().action(&key);
+
+        let command = match action {
+            Some(Action::ShowHelp) => {
+                event.prevent_default();
+                self.is_help_shown.toggle();
+                return;
+            }
+            Some(action) => action.command(&player_state.peek()),
+            None => track_index(&key).map(rradio_messages::Command::NthItem),
+        };
+
+        if let Some(command) = command {
+            event.prevent_default();
+            commands.send(command);
+        }
+    }
+}
+
+/// Handle key presses anywhere in the page, sending commands to the current server
+pub fn use_keyboard_shortcuts(
+    player_state: Signal<PlayerState>,
+    commands: Coroutine<rradio_messages::Command>,
+) -> KeyboardShortcuts {
+    let shortcuts = KeyboardShortcuts {
+        bindings: use_signal(KeyBindings::load),
+        is_help_shown: use_signal(|| false),
+        rebinding: use_signal(|| None),
+    };
+
+    let listener = use_hook(|| {
+        let runtime = Runtime::current().expect("unreachable: hooks are called within the runtime");
+
+        let listener = Rc::new(Closure::<dyn Fn(web_sys::KeyboardEvent)>::new(
+            move |event: web_sys::KeyboardEvent| {
+                let _guard = RuntimeGuard::new(runtime.clone());
+                shortcuts.handle_keydown(&event, player_state, commands);
+            },
+        ));
+
+        if let Err(err) = gloo_utils::document()
+            .add_event_listener_with_callback("keydown", listener.as_ref().as_ref().unchecked_ref())
+        {
+            error!("Failed to add keyboard shortcuts: {err:?}");
+        }
+
+        listener
+    });
+
+    use_drop(move || {
+        if let Err(err) = gloo_utils::document().remove_event_listener_with_callback(
+            "keydown",
+            listener.as_ref().as_ref().unchecked_ref(),
+        ) {
+            error!("Failed to remove keyboard shortcuts: {err:?}");
+        }
+    });
+
+    shortcuts
+}
+
+#[component]
+pub fn KeyboardShortcutsHelp(shortcuts: KeyboardShortcuts) -> Element {
+    let KeyboardShortcuts {
+        mut bindings,
+        mut is_help_shown,
+        mut rebinding,
+    } = shortcuts;
+
+    if !is_help_shown() {
+        return rsx! {};
+    }
+
+    let rebinding_action = rebinding();
+
+    let rows = Action::ALL.iter().map(|&action| {
+        let key = if rebinding_action == Some(action) {
+            String::from("Press a key…")
+        } else {
+            String::from(display_key(bindings.read().key(action)))
+        };
+
+        rsx! {
+            tr {
+                key: "{action:?}",
+                td { "{action.description()}" }
+                td { kbd { "{key}" } }
+                td {
+                    button {
+                        "type": "button",
+                        onclick: move |_| rebinding.set(Some(action)),
+                        "Change"
+                    }
+                }
+            }
+        }
+    });
+
+    rsx! {
+        div {
+            id: "keyboard-shortcuts",
+            role: "dialog",
+            aria_label: "Keyboard shortcuts",
+            table {
+                caption { "Keyboard Shortcuts" }
+                {rows}
+                tr {
+                    td { "Play track" }
+                    td { kbd { "1" } " - " kbd { "9" } ", " kbd { "0" } }
+                    td {}
+                }
+            }
+            footer {
+                button {
+                    "type": "button",
+                    onclick: move |_| bindings.write().reset(),
+                    "Reset to Defaults"
+                }
+                button {
+                    "type": "button",
+                    onclick: move |_| {
+                        rebinding.set(None);
+                        is_help_shown.set(false);
+                    },
+                    "Close"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_a_used_key_swaps_the_keys() {
+        let mut bindings = KeyBindings::default();
+
+        bindings.bind(Action::PlayPause, "m");
+
+        assert_eq!(bindings.key(Action::PlayPause), "m");
+        assert_eq!(bindings.key(Action::ToggleMute), " ");
+        assert_eq!(bindings.action("m"), Some(Action::PlayPause));
+        assert_eq!(bindings.action(" "), Some(Action::ToggleMute));
+    }
+
+    #[test]
+    fn binding_an_unused_key_frees_the_previous_key() {
+        let mut bindings = KeyBindings::default();
+
+        bindings.bind(Action::PlayPause, "p");
+
+        assert_eq!(bindings.key(Action::PlayPause), "p");
+        assert_eq!(bindings.action(" "), None);
+        assert_eq!(bindings.key(Action::ToggleMute), "m");
+    }
+
+    #[test]
+    fn digits_select_tracks() {
+        assert_eq!(track_index("1"), Some(0));
+        assert_eq!(track_index("9"), Some(8));
+        assert_eq!(track_index("0"), Some(9));
+        assert_eq!(track_index("10"), None);
+        assert_eq!(track_index("+1"), None);
+        assert_eq!(track_index("a"), None);
+    }
+
+    #[test]
+    fn modifiers_are_not_bound() {
+        for key in ["Shift", "Control", "Alt", "AltGraph", "Meta", "CapsLock"] {
+            assert!(is_modifier_key(key), "{}", key);
+        }
+
+        for key in ["?", "+", "m", " ", "Escape"] {
+            assert!(!is_modifier_key(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn saved_keys_are_merged_with_the_defaults() {
+        let bindings = KeyBindings::with_saved_keys(BTreeMap::from([
+            (Action::PlayPause, String::from("p")),
+            (Action::ShowHelp, String::from("h")),
+        ]));
+
+        assert_eq!(bindings.key(Action::PlayPause), "p");
+        assert_eq!(bindings.key(Action::ShowHelp), "h");
+        assert_eq!(bindings.key(Action::NextItem), "ArrowDown");
+        assert_eq!(bindings.key(Action::ToggleMute), "m");
+    }
+}
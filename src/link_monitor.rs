@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use crate::reconnect::Clock;
+
+pub const DEFAULT_SILENCE_TIMEOUT: Duration = Duration::from_secs(60);
+pub const SILENCE_TIMEOUT_STORAGE_KEY: &str = "RRADIO_SILENCE_TIMEOUT";
+
+/// Detects dead connections.
+///
+/// The server sends updates to `ping_times` regularly while it's playing, so these act as a heartbeat.
+/// If nothing is received for `silence_timeout` while playing, the connection is assumed to be dead.
+/// An idle or paused server sends nothing, so silence is expected then.
+pub struct LinkMonitor<C: Clock> {
+    clock: C,
+    silence_timeout: Duration,
+    last_received_at: Duration,
+    is_playing: bool,
+}
+
+impl<C: Clock> LinkMonitor<C> {
+    pub fn new(silence_timeout: Duration, clock: C) -> Self {
+        let now = clock.now();
+
+        Self {
+            clock,
+            silence_timeout,
+            last_received_at: now,
+            is_playing: false,
+        }
+    }
+
+    pub fn event_received(&mut self, event: &rradio_messages::Event) {
+        self.last_received_at = self.clock.now();
+
+        match event {
+            rradio_messages::Event::PlayerStateChanged(diff) => {
+                if let Some(pipeline_state) = &diff.pipeline_state {
+                    self.is_playing = *pipeline_state == rradio_messages::PipelineState::Playing;
+                }
+            }
+        }
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if !self.is_playing {
+            return Ok(());
+        }
+
+        let silence = self.clock.now().saturating_sub(self.last_received_at);
+
+        if silence > self.silence_timeout {
+            anyhow::bail!(
+                "Connection lost: nothing received for {}s",
+                silence.as_secs()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rradio_messages::{Event, PipelineState, PlayerStateDiff};
+
+    use super::*;
+    use crate::{reconnect::FakeClock, tests::empty_diff};
+
+    const SILENCE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    fn pipeline_state_changed(pipeline_state: PipelineState) -> Event {
+        Event::PlayerStateChanged(PlayerStateDiff {
+            pipeline_state: Some(pipeline_state),
+            ..empty_diff()
+        })
+    }
+
+    #[test]
+    fn idle_server_is_not_timed_out() {
+        let clock = FakeClock::new(0.0);
+        let mut link_monitor = LinkMonitor::new(SILENCE_TIMEOUT, &clock);
+
+        clock.advance(SILENCE_TIMEOUT * 10);
+        assert!(link_monitor.check().is_ok());
+
+        link_monitor.event_received(&pipeline_state_changed(PipelineState::Paused));
+
+        clock.advance(SILENCE_TIMEOUT * 10);
+        assert!(link_monitor.check().is_ok());
+    }
+
+    #[test]
+    fn silence_while_playing_times_out() {
+        let clock = FakeClock::new(0.0);
+        let mut link_monitor = LinkMonitor::new(SILENCE_TIMEOUT, &clock);
+
+        link_monitor.event_received(&pipeline_state_changed(PipelineState::Playing));
+
+        clock.advance(SILENCE_TIMEOUT);
+        assert!(link_monitor.check().is_ok());
+
+        // Heartbeats don't change the pipeline state
+        link_monitor.event_received(&Event::PlayerStateChanged(empty_diff()));
+
+        clock.advance(SILENCE_TIMEOUT);
+        assert!(link_monitor.check().is_ok());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(link_monitor.check().is_err());
+    }
+
+    #[test]
+    fn pausing_stops_the_timeout() {
+        let clock = FakeClock::new(0.0);
+        let mut link_monitor = LinkMonitor::new(SILENCE_TIMEOUT, &clock);
+
+        link_monitor.event_received(&pipeline_state_changed(PipelineState::Playing));
+        link_monitor.event_received(&pipeline_state_changed(PipelineState::Paused));
+
+        clock.advance(SILENCE_TIMEOUT * 2);
+        assert!(link_monitor.check().is_ok());
+    }
+}
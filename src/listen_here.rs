@@ -0,0 +1,216 @@
+use dioxus::{
+    logger::tracing::{error, warn},
+    prelude::*,
+};
+use web_sys::wasm_bindgen::JsCast;
+
+use crate::{local_storage, use_player_state_without_position, PlayerState};
+
+const VOLUME_STORAGE_KEY: &str = "RRADIO_LISTEN_HERE_VOLUME";
+const MUTES_SERVER_STORAGE_KEY: &str = "RRADIO_LISTEN_HERE_MUTES_SERVER";
+
+const AUDIO_ELEMENT_ID: &str = "listen-here-audio";
+const DEFAULT_VOLUME: u8 = 50;
+
+fn audio_element() -> Option<web_sys::HtmlMediaElement> {
+    gloo_utils::document()
+        .get_element_by_id(AUDIO_ELEMENT_ID)?
+        .dyn_into()
+        .ok()
+}
+
+/// The url of the current track, if the browser can play it. Tracks stored on the server can't be played.
+fn current_track_url(player_state: &PlayerState) -> Option<&str> {
+    let rradio_messages::CurrentStation::PlayingStation {
+        tracks: Some(tracks),
+        ..
+    } = player_state.current_station.as_ref()
+    else {
+        return None;
+    };
+
+    let track = tracks.get(player_state.current_track_index)?;
+
+    (!track.is_notification
+        && (track.url.starts_with("http://") || track.url.starts_with("https://")))
+    .then_some(track.url.as_str())
+}
+
+/// Play the current track in the browser as well as on the server.
+/// This is provided as context so that playback continues when switching views.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ListenHere {
+    is_enabled: Signal<bool>,
+    /// The volume of the browser, as a percentage, which is separate from the server volume
+    volume: Signal<u8>,
+    /// Whether the server is muted while listening here
+    mutes_server: Signal<bool>,
+    /// Whether the server was muted before it was muted for listening here, which is restored when listening stops
+    server_was_muted: Signal<Option<bool>>,
+}
+
+impl ListenHere {
+    pub fn use_context_provider() -> Self {
+        use_context_provider(|| Self {
+            is_enabled: Signal::new(false),
+            volume: Signal::new(
+                local_storage::load(VOLUME_STORAGE_KEY)
+                    .and_then(|volume| volume.parse().ok())
+                    .unwrap_or(DEFAULT_VOLUME)
+                    .min(100),
+            ),
+            mutes_server: Signal::new(local_storage::load_flag(MUTES_SERVER_STORAGE_KEY)),
+            server_was_muted: Signal::new(None),
+        })
+    }
+
+    fn set_is_enabled(
+        mut self,
+        is_enabled: bool,
+        is_server_muted: bool,
+        commands: Coroutine<rradio_messages::Command>,
+    ) {
+        self.is_enabled.set(is_enabled);
+
+        if is_enabled {
+            if *self.mutes_server.peek() {
+                self.server_was_muted.set(Some(is_server_muted));
+                commands.send(rradio_messages::Command::SetIsMuted(true));
+            }
+        } else if let Some(was_muted) = self.server_was_muted.take() {
+            commands.send(rradio_messages::Command::SetIsMuted(was_muted));
+        }
+    }
+
+    fn set_volume(mut self, volume: u8) {
+        self.volume.set(volume);
+
+        if let Err(err) = local_storage::save(VOLUME_STORAGE_KEY, Some(&volume.to_string())) {
+            error!("{err:#}");
+        }
+    }
+
+    fn set_mutes_server(mut self, mutes_server: bool) {
+        self.mutes_server.set(mutes_server);
+
+        if let Err(err) = local_storage::save_flag(MUTES_SERVER_STORAGE_KEY, mutes_server) {
+            error!("{err:#}");
+        }
+    }
+}
+
+/// The audio element, which is kept outside of the views
+#[component]
+pub fn ListenHereAudio(player_state: Signal<PlayerState>) -> Element {
+    let listen_here = use_context::<ListenHere>();
+
+    let current_player_state = use_player_state_without_position(player_state)();
+
+    let url = current_track_url(&current_player_state).map(String::from);
+    let is_playing = current_player_state.pipeline_state == rradio_messages::PipelineState::Playing;
+
+    use_effect(move || {
+        let is_enabled = (listen_here.is_enabled)();
+        let volume = (listen_here.volume)();
+
+        if let Some(audio) = audio_element().filter(|_| is_enabled) {
+            audio.set_volume(f64::from(volume) / 100.0);
+        }
+    });
+
+    use_effect(use_reactive!(|is_playing| {
+        let Some(audio) = audio_element() else {
+            return;
+        };
+
+        if is_playing {
+            if let Err(err) = audio.play() {
+                warn!("Failed to play {AUDIO_ELEMENT_ID}: {err:?}");
+            }
+        } else if let Err(err) = audio.pause() {
+            warn!("Failed to pause {AUDIO_ELEMENT_ID}: {err:?}");
+        }
+    }));
+
+    if !(listen_here.is_enabled)() {
+        return rsx! {};
+    }
+
+    let Some(url) = url else {
+        return rsx! {};
+    };
+
+    rsx! {
+        audio {
+            id: AUDIO_ELEMENT_ID,
+            src: "{url}",
+            autoplay: is_playing,
+            // The element is created when listening is enabled or a track URL appears, after the volume effect has already run
+            onmounted: move |_| {
+                if let Some(audio) = audio_element() {
+                    audio.set_volume(f64::from(*listen_here.volume.peek()) / 100.0);
+                }
+            },
+            // Catch up with the server when a track which isn't a live stream starts
+            onloadedmetadata: move |_| {
+                if let (Some(audio), Some(track_position)) = (audio_element(), player_state.peek().track_position) {
+                    audio.set_current_time(track_position.as_secs_f64());
+                }
+            },
+        }
+    }
+}
+
+pub fn can_listen_here(player_state: &PlayerState) -> bool {
+    current_track_url(player_state).is_some()
+}
+
+#[component]
+pub fn ListenHereView(can_play: bool, is_server_muted: bool) -> Element {
+    let commands = use_coroutine_handle::<rradio_messages::Command>();
+    let listen_here = use_context::<ListenHere>();
+
+    let is_enabled = (listen_here.is_enabled)();
+    let volume = (listen_here.volume)();
+    let mutes_server = (listen_here.mutes_server)();
+
+    rsx! {
+        div {
+            id: "listen-here",
+            button {
+                "type": "button",
+                title: "Play the current track in this browser",
+                aria_pressed: "{is_enabled}",
+                onclick: move |_| listen_here.set_is_enabled(!is_enabled, is_server_muted, commands),
+                if is_enabled { "🎧 Stop Listening Here" } else { "🎧 Listen Here" }
+            }
+            if is_enabled {
+                if !can_play {
+                    output { "This track can't be played in the browser" }
+                }
+                label {
+                    "🔉"
+                    input {
+                        "type": "range",
+                        min: "0",
+                        max: "100",
+                        value: "{volume}",
+                        oninput: move |ev| match ev.value().parse() {
+                            Ok(volume) => listen_here.set_volume(volume),
+                            Err(err) => warn!("Failed to handle volume {:?}: {err}", ev.value()),
+                        },
+                    }
+                    "🔊"
+                }
+            }
+            label {
+                input {
+                    "type": "checkbox",
+                    checked: mutes_server,
+                    onchange: move |ev| listen_here.set_mutes_server(ev.checked()),
+                }
+                "Mute the server while listening here"
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! String settings kept in the browser's local storage
+
+use gloo_storage::Storage;
+
+pub fn load(key: &str) -> Option<String> {
+    gloo_storage::LocalStorage::raw()
+        .get_item(key)
+        .expect("unreachable: get_item does not throw an exception")
+}
+
+/// Save `value`, or remove the setting if `value` is `None`
+pub fn save(key: &str, value: Option<&str>) -> anyhow::Result<()> {
+    let storage = gloo_storage::LocalStorage::raw();
+
+    match value {
+        Some(value) => storage.set_item(key, value),
+        None => storage.remove_item(key),
+    }
+    .map_err(|err| anyhow::anyhow!("Failed to save {}: {:?}", key, err))
+}
+
+/// Flags are set if the setting is present, whatever its value
+pub fn load_flag(key: &str) -> bool {
+    load(key).is_some()
+}
+
+pub fn save_flag(key: &str, is_set: bool) -> anyhow::Result<()> {
+    save(key, is_set.then_some("true"))
+}
@@ -8,7 +8,6 @@ use dioxus::{
     logger::tracing::{self, warn},
     prelude::*,
 };
-use futures_util::{FutureExt, SinkExt, StreamExt};
 use gloo_storage::Storage;
 
 use rradio_messages::ArcStr;
@@ -19,26 +18,77 @@ use fast_eq_rc::FastEqRc;
 mod update_from_diff;
 use update_from_diff::UpdateFromDiff;
 
+mod api_endpoint;
+use api_endpoint::ApiEndpoint;
+
+mod reconnect;
+use reconnect::{BrowserClock, Clock};
+
+mod command_queue;
+mod error_history;
+mod event_recorder;
+mod keyboard_shortcuts;
+mod link_monitor;
+mod listen_here;
+mod local_storage;
+mod media_session;
+mod transport;
+
+mod route;
+use route::{Route, SettingsTab};
+mod server_notice;
+mod startup_error;
+
+mod connection;
+use connection::ServerConnection;
+
+mod dashboard_view;
 mod debug_view;
+mod playback_status;
 mod player_state_view;
 mod podcasts_view;
+mod position_interpolator;
+mod servers_view;
+mod settings_view;
 mod track_position_slider;
 
+const LOGGING_STORAGE_KEY: &str = "RRADIO_LOGGING";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppView {
     PlayerState,
     Podcasts,
+    Dashboard,
+    Settings,
+    Errors,
     Debug,
 }
 
 impl AppView {
+    const ALL: [Self; 6] = [
+        Self::PlayerState,
+        Self::Podcasts,
+        Self::Dashboard,
+        Self::Settings,
+        Self::Errors,
+        Self::Debug,
+    ];
+
     fn classname(self) -> &'static str {
         match self {
             AppView::PlayerState => "player-state",
             AppView::Podcasts => "podcasts",
+            AppView::Dashboard => "dashboard",
+            AppView::Settings => "settings",
+            AppView::Errors => "errors",
             AppView::Debug => "debug",
         }
     }
+
+    /// Views showing the player state are greyed out while it may be out of date
+    fn shows_player_state(self) -> bool {
+        matches!(self, AppView::PlayerState | AppView::Podcasts)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -47,6 +97,14 @@ pub enum ConnectionState {
     Connected,
     Disconnected,
     ConnectionError(ArcStr),
+    WaitingToReconnect {
+        error: ArcStr,
+        retry_at: Duration,
+    },
+    IncompatibleServer {
+        client: ArcStr,
+        server: Option<ArcStr>,
+    },
 }
 
 impl ConnectionState {
@@ -54,7 +112,15 @@ impl ConnectionState {
         move |result: anyhow::Result<()>| {
             connection_state.clone().set(match result {
                 Ok(()) => Self::Disconnected,
-                Err(err) => Self::ConnectionError(rradio_messages::arcstr::format!("{:#}", err)),
+                Err(err) => match err.downcast_ref::<transport::IncompatibleServerError>() {
+                    Some(transport::IncompatibleServerError { client, server }) => {
+                        Self::IncompatibleServer {
+                            client: client.clone(),
+                            server: server.clone(),
+                        }
+                    }
+                    None => Self::ConnectionError(rradio_messages::arcstr::format!("{:#}", err)),
+                },
             });
         }
     }
@@ -76,6 +142,27 @@ pub struct PlayerState {
     pub latest_error: FastEqRc<Option<rradio_messages::LatestError>>,
 }
 
+impl PlayerState {
+    /// Replace the entire state, using the first diff sent by the server after connecting
+    pub fn resync(&mut self, diff: rradio_messages::PlayerStateDiff) {
+        *self = Self::default();
+        self.update_from_diff(diff);
+    }
+
+    fn without_track_position(&self) -> Self {
+        Self {
+            track_position: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// The player state without the track position, which changes every second while a track is playing.
+/// Components which read this instead of the whole state aren't re-rendered as the track advances.
+fn use_player_state_without_position(player_state: Signal<PlayerState>) -> Memo<PlayerState> {
+    use_memo(move || player_state.read().without_track_position())
+}
+
 impl UpdateFromDiff<rradio_messages::PlayerStateDiff> for PlayerState {
     fn update_from_diff(&mut self, diff: rradio_messages::PlayerStateDiff) {
         let rradio_messages::PlayerStateDiff {
@@ -110,13 +197,23 @@ impl UpdateFromDiff<rradio_messages::PlayerStateDiff> for PlayerState {
     }
 }
 
+/// Formats as `mm:ss`, or `h:mm:ss` if the duration is at least an hour.
+/// The alternate flag, i.e. `{:#}`, always includes the hours.
 struct DisplayDuration(Duration);
 
 impl fmt::Display for DisplayDuration {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let secs = self.0.as_secs();
 
-        write!(f, "{:02}:{:02}", secs / 60, secs % 60)
+        let hours = secs / 3600;
+        let minutes = (secs / 60) % 60;
+        let seconds = secs % 60;
+
+        if hours > 0 || f.alternate() {
+            write!(f, "{hours}:{minutes:02}:{seconds:02}")
+        } else {
+            write!(f, "{minutes:02}:{seconds:02}")
+        }
     }
 }
 
@@ -133,194 +230,396 @@ where
         }
     }
 }
-enum AppCommand {
-    Command(rradio_messages::Command),
-    Event(Result<gloo_net::websocket::Message, gloo_net::websocket::WebSocketError>),
+
+/// The current time, updated every second
+fn use_now() -> Signal<Duration> {
+    let mut now = use_signal(|| BrowserClock.now());
+
+    use_future(move || async move {
+        loop {
+            gloo_timers::future::sleep(Duration::from_secs(1)).await;
+            now.set(BrowserClock.now());
+        }
+    });
+
+    now
+}
+
+struct DisplayElapsed(Duration);
+
+impl fmt::Display for DisplayElapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+
+        match secs {
+            0..=59 => write!(f, "{secs}s"),
+            60..=3599 => write!(f, "{}m", secs / 60),
+            _ => write!(f, "{}h {}m", secs / 3600, (secs / 60) % 60),
+        }
+    }
+}
+
+#[component]
+fn ConnectionDetailsView(connection: ServerConnection) -> Element {
+    let now = use_now();
+
+    let pending_commands = match (connection.pending_commands)() {
+        0 => None,
+        1 => Some(rsx! { " (1 command waiting to be sent)" }),
+        count => Some(rsx! { " ({count} commands waiting to be sent)" }),
+    };
+
+    let last_updated = (connection.last_updated)().map(|last_updated| {
+        let elapsed = DisplayElapsed(now().saturating_sub(last_updated));
+        rsx! { " Last updated {elapsed} ago." }
+    });
+
+    rsx! {
+        {pending_commands}
+        {last_updated}
+    }
+}
+
+#[component]
+fn ReconnectCountdownView(
+    error: ArcStr,
+    retry_at: Duration,
+    connection: ServerConnection,
+) -> Element {
+    let now = use_now();
+
+    let mut retry_now = connection.retry_now;
+
+    let remaining = retry_at.saturating_sub(now());
+    let seconds_remaining = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+
+    rsx! {
+        header {
+            id: "connection-message",
+            output {
+                "{error} - Reconnecting in {seconds_remaining}s…"
+                ConnectionDetailsView { connection }
+                button {
+                    "type": "button",
+                    onclick: move |_| {
+                        if let Some(retry_now) = retry_now.take() {
+                            retry_now.abort();
+                        }
+                    },
+                    "Retry now"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LinkQualityView(latency: Signal<Option<Duration>>) -> Element {
+    let Some(latency) = latency() else {
+        return rsx! {};
+    };
+
+    let latency = latency.as_millis();
+
+    let quality = match latency {
+        0..=199 => "good",
+        200..=999 => "fair",
+        _ => "poor",
+    };
+
+    rsx! {
+        output {
+            id: "link-quality",
+            class: "{quality}",
+            title: "Round trip time",
+            "{latency} ms"
+        }
+    }
+}
+
+#[component]
+fn IncompatibleServerView(client: ArcStr, server: Option<ArcStr>) -> Element {
+    let advice = match server
+        .as_deref()
+        .and_then(|server| transport::compare_api_versions(&client, server))
+    {
+        Some(std::cmp::Ordering::Less) => {
+            "this web client is older than the rradio server, please upgrade the web client"
+        }
+        Some(std::cmp::Ordering::Greater) => {
+            "the rradio server is older than this web client, please upgrade the rradio server"
+        }
+        Some(std::cmp::Ordering::Equal) | None => {
+            "please make sure that this web client and the rradio server are the same version"
+        }
+    };
+
+    let server = server.as_deref().unwrap_or("unknown");
+
+    rsx! {
+        header {
+            id: "connection-message",
+            output {
+                "Incompatible server: {advice}"
+                br {}
+                small { "Client API version: {client}, server API version: {server}" }
+            }
+        }
+    }
 }
 
 #[component]
-fn ConnectionStateView(connection_state: Signal<ConnectionState>) -> Element {
-    let connection_state = connection_state();
+fn ConnectionStateView(connection: ServerConnection) -> Element {
+    let connection_state = (connection.connection_state)();
     let message = match &connection_state {
         ConnectionState::Connecting => "Connecting...",
-        ConnectionState::Connected => return rsx! {},
+        ConnectionState::Connected => {
+            return rsx! { LinkQualityView { latency: connection.latency } }
+        }
         ConnectionState::Disconnected => "RRadio has terminated",
         ConnectionState::ConnectionError(err) => err,
+        ConnectionState::WaitingToReconnect { error, retry_at } => {
+            return rsx! { ReconnectCountdownView { error: error.clone(), retry_at: *retry_at, connection } }
+        }
+        ConnectionState::IncompatibleServer { client, server } => {
+            return rsx! { IncompatibleServerView { client: client.clone(), server: server.clone() } }
+        }
     };
 
     rsx! {
         header {
             id: "connection-message",
-            output { "{message}" }
+            output {
+                "{message}"
+                ConnectionDetailsView { connection }
+            }
         }
     }
 }
 
 #[component]
 fn RootView() -> Element {
-    let mut connection_state = use_signal(|| ConnectionState::Connecting);
-    let mut player_state = use_signal(PlayerState::default);
-
-    use_coroutine(move |mut commands| {
-        async move {
-            let host = gloo_storage::LocalStorage::raw()
-                .get_item("RRADIO_SERVER")
-                .expect("unreachable: get_item does not throw an exception")
-                .map_or_else(
-                    || {
-                        web_sys::window()
-                            .context("No Window!")?
-                            .location()
-                            .host()
-                            .map_err(|err| anyhow::anyhow!("No hostname: {:?}", err))
-                    },
-                    Ok,
-                )?;
-
-            let api_url = format!("ws://{host}/api");
-
-            let mut is_first_connection_attempt = true;
-
-            loop {
-                let result = async {
-                    let (mut websocket_tx, websocket_rx) =
-                        gloo_net::websocket::futures::WebSocket::open_with_protocol(
-                            &api_url,
-                            rradio_messages::API_VERSION_HEADER.trim(),
-                        )
-                        .map_err(|err| anyhow::anyhow!("Failed to open websocket: {err:?}"))?
-                        .split();
-
-                    is_first_connection_attempt = false;
-                    connection_state.set(ConnectionState::Connected);
-
-                    let app_commands = futures_util::stream::select(
-                        (&mut commands).map(AppCommand::Command),
-                        websocket_rx.map(AppCommand::Event),
-                    );
-
-                    futures_util::pin_mut!(app_commands);
-
-                    while let Some(app_command) = app_commands.next().await {
-                        match app_command {
-                            AppCommand::Command(rradio_command) => {
-                                let mut buffer = Vec::new();
-                                rradio_command
-                                    .encode(&mut buffer)
-                                    .context("Failed to encode Command")?;
-
-                                websocket_tx
-                                    .send(gloo_net::websocket::Message::Bytes(buffer))
-                                    .await
-                                    .map_err(|err| {
-                                        anyhow::anyhow!("Failed to send websocket message: {err}")
-                                    })?;
-                            }
-                            AppCommand::Event(Err(
-                                gloo_net::websocket::WebSocketError::ConnectionClose(_),
-                            )) => {
-                                break;
-                            }
-                            AppCommand::Event(rradio_event) => {
-                                match rradio_event.map_err(|err| {
-                                    anyhow::anyhow!("Failed to receive websocket message: {err}")
-                                })? {
-                                    gloo_net::websocket::Message::Text(message) => {
-                                        warn!("Ignoring text message: {message:?}");
-                                    }
-                                    gloo_net::websocket::Message::Bytes(mut buffer) => {
-                                        match rradio_messages::Event::decode(&mut buffer)
-                                            .context("Failed to decode Event")?
-                                        {
-                                            rradio_messages::Event::PlayerStateChanged(diff) => {
-                                                player_state.with_mut(|current_player_state| {
-                                                    current_player_state.update_from_diff(diff);
-                                                });
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    Ok(())
-                }
-                .await;
-
-                match result {
-                    Ok(()) => return Ok(()),
-                    Err(err) if is_first_connection_attempt => return Err(err),
-                    Err(err) => {
-                        connection_state.set(ConnectionState::ConnectionError(
-                            rradio_messages::arcstr::format!("{:#}", err),
-                        ));
-                    }
-                }
-
-                // Wait and then try to reconnect
-                gloo_timers::future::sleep(std::time::Duration::from_secs(3)).await;
-            }
+    let connection = connection::use_server_connection(ApiEndpoint::load);
+
+    let ServerConnection {
+        connection_state,
+        player_state,
+        notices,
+        errors,
+        commands,
+        ..
+    } = connection;
+
+    let keyboard_shortcuts = keyboard_shortcuts::use_keyboard_shortcuts(player_state, commands);
+    media_session::use_media_session(player_state, commands);
+
+    let route = route::use_route();
+    let view = route.view();
+
+    // Grey out the player while its state may be out of date
+    use_effect(use_reactive!(|view| {
+        set_app_class(
+            "stale",
+            view.shows_player_state() && connection_state() != ConnectionState::Connected,
+        );
+    }));
+
+    use_effect(use_reactive!(|view| {
+        for other_view in AppView::ALL {
+            set_app_class(other_view.classname(), other_view == view);
         }
-        .map(ConnectionState::handle_closed(connection_state))
-    });
+    }));
 
-    let player_state = player_state();
+    servers_view::SavedServers::use_context_provider();
+    listen_here::ListenHere::use_context_provider();
 
-    let app = match use_context() {
-        AppView::PlayerState => {
+    // Outside of the views, so that listening continues when switching views
+    let listen_here_audio = rsx! { listen_here::ListenHereAudio { player_state } };
+
+    let app = match route {
+        Route::PlayerState => {
             rsx! { player_state_view::PlayerStateView { player_state } }
         }
-        AppView::Podcasts => rsx! { podcasts_view::PodcastsView { player_state } },
-        AppView::Debug => {
-            rsx! { debug_view::DebugView { connection_state, player_state } }
+        Route::Podcasts { podcast_url } => {
+            rsx! { podcasts_view::PodcastsView { player_state, podcast_url } }
+        }
+        Route::Dashboard => rsx! { dashboard_view::DashboardView {} },
+        Route::Settings { tab } => rsx! { settings_view::SettingsView { tab } },
+        Route::Errors => rsx! { error_history::ErrorHistoryView { errors } },
+        Route::Debug => {
+            rsx! { debug_view::DebugView { connection, player_state } }
         }
     };
 
     rsx! {
-        ConnectionStateView { connection_state }
+        ConnectionStateView { connection }
+        server_notice::ServerNoticesView { notices }
+        error_history::ErrorToastsView { errors }
+        keyboard_shortcuts::KeyboardShortcutsHelp { shortcuts: keyboard_shortcuts }
+        {listen_here_audio}
         nav {
-            a { href: "?player", "Player" },
-            a { href: "?podcasts", "Podcasts" }
-            a { href: "?debug", "Debug" }
+            a { href: Route::PlayerState.href(), "Player" },
+            a { href: Route::Podcasts { podcast_url: None }.href(), "Podcasts" }
+            a { href: Route::Dashboard.href(), "Rooms" }
+            a { href: Route::Settings { tab: SettingsTab::default() }.href(), "Settings" }
+            a {
+                href: Route::Errors.href(),
+                if errors.read().is_empty() { "Errors" } else { "Errors ({errors.read().len()})" }
+            }
+            a { href: Route::Debug.href(), "Debug" }
+            servers_view::ServerSelectView {}
         }
         {app}
     }
 }
 
-fn main() {
-    dioxus::logger::init(
-        gloo_storage::LocalStorage::raw()
-            .get("RRADIO_LOGGING")
-            .unwrap()
-            .map_or(tracing::Level::INFO, |level| {
-                level.parse().expect("Logging level")
-            }),
-    )
-    .expect("logger failed to init");
-
-    let app_view = match gloo_utils::window()
-        .location()
-        .search()
-        .expect("search")
-        .as_str()
-    {
-        "?podcast" | "?podcasts" => AppView::Podcasts,
-        "?debug" => AppView::Debug,
-        _ => AppView::PlayerState,
+fn set_app_class(classname: &str, is_set: bool) {
+    if let Some(app) = gloo_utils::document().get_element_by_id("app") {
+        if let Err(err) = app.class_list().toggle_with_force(classname, is_set) {
+            warn!("Failed to update class list: {err:?}");
+        }
+    }
+}
+
+fn load_logging_level() -> anyhow::Result<tracing::Level> {
+    let Some(level) = gloo_storage::LocalStorage::raw()
+        .get(LOGGING_STORAGE_KEY)
+        .map_err(|err| anyhow::anyhow!("Failed to read {LOGGING_STORAGE_KEY}: {err:?}"))?
+    else {
+        return Ok(tracing::Level::INFO);
     };
 
+    level
+        .parse()
+        .with_context(|| format!("Invalid {LOGGING_STORAGE_KEY} {level:?}"))
+}
+
+fn main() {
+    let mut errors = Vec::new();
+
+    let logging_level = load_logging_level().unwrap_or_else(|err| {
+        errors.push(err);
+        tracing::Level::INFO
+    });
+
+    if let Err(err) = dioxus::logger::init(logging_level) {
+        errors.push(anyhow::anyhow!("Failed to initialise logging: {err}"));
+    }
+
+    if let Err(err) = route::redirect_legacy_query() {
+        errors.push(err);
+    }
+
     let root_element = "app";
 
-    let main = gloo_utils::document()
-        .get_element_by_id(root_element)
-        .expect(r#"no element "app""#);
+    let main = gloo_utils::document().get_element_by_id(root_element);
+
+    if main.is_none() {
+        errors.push(anyhow::anyhow!("No element {root_element:?}"));
+    }
+
+    let Some(main) = main.filter(|_| errors.is_empty()) else {
+        for err in &errors {
+            tracing::error!("{err:#}");
+        }
+
+        startup_error::show(
+            "RRadio failed to start",
+            errors.iter().map(|err| format!("{err:#}")),
+        );
+
+        return;
+    };
 
-    main.set_class_name(app_view.classname());
     main.set_inner_html("");
 
     LaunchBuilder::new()
         .with_cfg(dioxus::web::Config::new().rootelement(main))
-        .with_context(app_view)
         .launch(RootView);
+
+    // Installed last, so that it wraps any hook set up by logging or launching, e.g. the console panic hook.
+    // Launching only spawns the app, which is first rendered after `main` returns.
+    startup_error::set_panic_hook();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// A diff which changes nothing
+    pub fn empty_diff() -> rradio_messages::PlayerStateDiff {
+        rradio_messages::PlayerStateDiff {
+            pipeline_state: None,
+            current_station: None,
+            pause_before_playing: None,
+            current_track_index: None,
+            current_track_tags: None,
+            is_muted: None,
+            volume: None,
+            buffering: None,
+            track_duration: None,
+            track_position: None,
+            ping_times: None,
+            latest_error: None,
+        }
+    }
+
+    #[test]
+    fn resync_discards_previous_session() {
+        let mut player_state = PlayerState::default();
+
+        player_state.update_from_diff(rradio_messages::PlayerStateDiff {
+            pipeline_state: Some(rradio_messages::PipelineState::Playing),
+            pause_before_playing: Some(Some(Duration::from_secs(5))),
+            current_track_index: Some(3),
+            current_track_tags: Some(Some(rradio_messages::TrackTags {
+                title: Some("Tags".into()),
+                organisation: None,
+                artist: None,
+                album: None,
+                genre: None,
+                image: None,
+                comment: None,
+            })),
+            is_muted: Some(true),
+            volume: Some(42),
+            buffering: Some(50),
+            track_duration: Some(Some(Duration::from_secs(300))),
+            track_position: Some(Some(Duration::from_secs(30))),
+            ..empty_diff()
+        });
+
+        player_state.resync(empty_diff());
+
+        // FastEqRc compares by pointer, so compare the contents instead
+        assert_eq!(
+            format!("{player_state:?}"),
+            format!("{:?}", PlayerState::default())
+        );
+    }
+
+    #[test]
+    fn durations_include_hours_only_when_needed() {
+        let display = |secs| DisplayDuration(Duration::from_secs(secs)).to_string();
+
+        assert_eq!(display(0), "00:00");
+        assert_eq!(display(65), "01:05");
+        assert_eq!(display(3599), "59:59");
+        assert_eq!(display(3600), "1:00:00");
+        assert_eq!(display(36_000 + 62), "10:01:02");
+    }
+
+    #[test]
+    fn alternate_durations_always_include_hours() {
+        assert_eq!(
+            format!("{:#}", DisplayDuration(Duration::from_secs(65))),
+            "0:01:05"
+        );
+        assert_eq!(
+            format!("{:#}", DisplayDuration(Duration::from_millis(999))),
+            "0:00:00"
+        );
+    }
 }
@@ -0,0 +1,239 @@
+use std::{rc::Rc, time::Duration};
+
+use dioxus::{
+    logger::tracing::{debug, warn},
+    prelude::*,
+};
+use rradio_messages::{Command, PipelineState};
+use web_sys::{
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    MediaSession, MediaSessionAction, MediaSessionActionDetails, MediaSessionPlaybackState,
+};
+
+use crate::PlayerState;
+
+const DEFAULT_SEEK_OFFSET: Duration = Duration::from_secs(10);
+
+type ActionHandler = Closure<dyn Fn(MediaSessionActionDetails)>;
+
+/// [`MediaSession::set_action_handler`], which returns an error instead of throwing if the browser doesn't support `action`
+fn try_set_action_handler(
+    media_session: &MediaSession,
+    action: MediaSessionAction,
+    handler: &web_sys::js_sys::Function,
+) -> Result<(), JsValue> {
+    web_sys::js_sys::Reflect::get(media_session, &JsValue::from_str("setActionHandler"))?
+        .dyn_into::<web_sys::js_sys::Function>()?
+        .call2(media_session, &JsValue::from(action), handler)
+        .map(drop)
+}
+
+/// `None` if the browser doesn't support the Media Session API
+fn media_session() -> Option<MediaSession> {
+    let navigator = gloo_utils::window().navigator();
+
+    web_sys::js_sys::Reflect::has(&navigator, &JsValue::from_str("mediaSession"))
+        .unwrap_or(false)
+        .then(|| navigator.media_session())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TrackMetadata {
+    title: String,
+    artist: String,
+    album: String,
+    image: Option<String>,
+}
+
+impl TrackMetadata {
+    /// Tags from the stream take priority over the playlist entry
+    fn new(player_state: &PlayerState) -> Option<Self> {
+        let rradio_messages::CurrentStation::PlayingStation { title, tracks, .. } =
+            player_state.current_station.as_ref()
+        else {
+            return None;
+        };
+
+        let track = tracks
+            .as_deref()
+            .and_then(|tracks| tracks.get(player_state.current_track_index));
+        let tags = player_state.current_track_tags.as_ref().as_ref();
+
+        let pick = |from_tags: Option<&str>, from_track: Option<&str>| {
+            String::from(from_tags.or(from_track).unwrap_or_default())
+        };
+
+        Some(Self {
+            title: pick(
+                tags.and_then(|tags| tags.title.as_deref()),
+                track.and_then(|track| track.title.as_deref()),
+            ),
+            artist: pick(
+                tags.and_then(|tags| tags.artist.as_deref()),
+                track.and_then(|track| track.artist.as_deref()),
+            ),
+            album: pick(
+                tags.and_then(|tags| tags.album.as_deref()),
+                track
+                    .and_then(|track| track.album.as_deref())
+                    .or(title.as_deref()),
+            ),
+            image: tags
+                .and_then(|tags| tags.image.as_deref())
+                .map(String::from),
+        })
+    }
+
+    fn to_media_metadata(&self) -> Result<web_sys::MediaMetadata, JsValue> {
+        let metadata = web_sys::MediaMetadata::new()?;
+
+        metadata.set_title(&self.title);
+        metadata.set_artist(&self.artist);
+        metadata.set_album(&self.album);
+
+        if let Some(image) = &self.image {
+            metadata.set_artwork(&[web_sys::MediaImage::new(image)]);
+        }
+
+        Ok(metadata)
+    }
+}
+
+fn update_position_state(media_session: &MediaSession, player_state: &PlayerState) {
+    match (player_state.track_position, player_state.track_duration) {
+        (Some(position), Some(duration)) if position <= duration => {
+            let position_state = web_sys::MediaPositionState::new();
+            position_state.set_duration(duration.as_secs_f64());
+            position_state.set_position(position.as_secs_f64());
+            position_state.set_playback_rate(1.0);
+
+            media_session.set_position_state_with_state(&position_state);
+        }
+        // Live streams have no position
+        _ => media_session.set_position_state(),
+    }
+}
+
+fn action_command(
+    action: MediaSessionAction,
+    details: &MediaSessionActionDetails,
+    player_state: &PlayerState,
+) -> Option<Command> {
+    let seek_offset = || {
+        details
+            .get_seek_offset()
+            .map_or(DEFAULT_SEEK_OFFSET, Duration::from_secs_f64)
+    };
+
+    // There is only a command to toggle between playing and paused
+    let is_playing = player_state.pipeline_state == PipelineState::Playing;
+
+    match action {
+        MediaSessionAction::Play => (!is_playing).then_some(Command::PlayPause),
+        MediaSessionAction::Pause => is_playing.then_some(Command::PlayPause),
+        MediaSessionAction::Previoustrack => Some(Command::SmartPreviousItem),
+        MediaSessionAction::Nexttrack => Some(Command::NextItem),
+        MediaSessionAction::Seekbackward => Some(Command::SeekBackwards(seek_offset())),
+        MediaSessionAction::Seekforward => Some(Command::SeekForwards(seek_offset())),
+        MediaSessionAction::Seekto => details
+            .get_seek_time()
+            .map(|seek_time| Command::SeekTo(Duration::from_secs_f64(seek_time.max(0.0)))),
+        _ => None,
+    }
+}
+
+const ACTIONS: [MediaSessionAction; 7] = [
+    MediaSessionAction::Play,
+    MediaSessionAction::Pause,
+    MediaSessionAction::Previoustrack,
+    MediaSessionAction::Nexttrack,
+    MediaSessionAction::Seekbackward,
+    MediaSessionAction::Seekforward,
+    MediaSessionAction::Seekto,
+];
+
+/// Publish the current track to the operating system's media controls, such as on a phone's lock screen,
+/// and send their actions to the server.
+///
+/// Note that browsers might only show the controls while the page itself is playing audio.
+pub fn use_media_session(player_state: Signal<PlayerState>, commands: Coroutine<Command>) {
+    let handlers = use_hook(|| {
+        let Some(media_session) = media_session() else {
+            debug!("Media Session API not supported");
+            return Rc::new(Vec::new());
+        };
+
+        let runtime = Runtime::current().expect("unreachable: hooks are called within the runtime");
+
+        let handlers = ACTIONS
+            .iter()
+            .filter_map(|&action| {
+                let runtime = runtime.clone();
+
+                let handler = ActionHandler::new(move |details: MediaSessionActionDetails| {
+                    let _guard = RuntimeGuard::new(runtime.clone());
+
+                    if let Some(command) = action_command(action, &details, &player_state.peek()) {
+                        commands.send(command);
+                    }
+                });
+
+                // Older browsers don't support every action, e.g. "seekto"
+                if let Err(err) =
+                    try_set_action_handler(&media_session, action, handler.as_ref().unchecked_ref())
+                {
+                    debug!("Media session action {action:?} not supported: {err:?}");
+                    return None;
+                }
+
+                Some((action, handler))
+            })
+            .collect::<Vec<_>>();
+
+        Rc::new(handlers)
+    });
+
+    use_drop(move || {
+        if let Some(media_session) = media_session() {
+            for (action, _) in handlers.iter() {
+                media_session.set_action_handler(*action, None);
+            }
+        }
+    });
+
+    let metadata = use_memo(move || TrackMetadata::new(&player_state.read()));
+
+    use_effect(move || {
+        let Some(media_session) = media_session() else {
+            return;
+        };
+
+        match metadata
+            .read()
+            .as_ref()
+            .map(TrackMetadata::to_media_metadata)
+        {
+            Some(Ok(metadata)) => media_session.set_metadata(Some(&metadata)),
+            Some(Err(err)) => warn!("Failed to create media metadata: {err:?}"),
+            None => media_session.set_metadata(None),
+        }
+    });
+
+    use_effect(move || {
+        let Some(media_session) = media_session() else {
+            return;
+        };
+
+        let player_state = player_state.read();
+
+        media_session.set_playback_state(match player_state.pipeline_state {
+            PipelineState::Playing => MediaSessionPlaybackState::Playing,
+            PipelineState::Paused => MediaSessionPlaybackState::Paused,
+            PipelineState::VoidPending | PipelineState::Null | PipelineState::Ready => {
+                MediaSessionPlaybackState::None
+            }
+        });
+
+        update_position_state(&media_session, &player_state);
+    });
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use dioxus::prelude::*;
+
+use rradio_messages::PipelineState;
+
+use crate::{
+    reconnect::{BrowserClock, Clock},
+    use_now, FastEqRc,
+};
+
+/// What the listener hears, derived from the pipeline state and buffering percentage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Stopped,
+    Buffering { percent: u8 },
+    Paused,
+    Playing,
+}
+
+impl PlaybackStatus {
+    pub fn new(pipeline_state: PipelineState, buffering: u8) -> Self {
+        match pipeline_state {
+            // A paused track keeps its buffering percentage, but isn't waiting on it
+            PipelineState::Playing if buffering < 100 => Self::Buffering { percent: buffering },
+            PipelineState::Playing => Self::Playing,
+            PipelineState::Paused => Self::Paused,
+            PipelineState::VoidPending | PipelineState::Null | PipelineState::Ready => {
+                Self::Stopped
+            }
+        }
+    }
+
+    fn classname(self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Buffering { .. } => "buffering",
+            Self::Paused => "paused",
+            Self::Playing => "playing",
+        }
+    }
+}
+
+#[component]
+pub fn PlaybackStatusView(pipeline_state: PipelineState, buffering: u8) -> Element {
+    let status = PlaybackStatus::new(pipeline_state, buffering);
+
+    let classname = status.classname();
+
+    let contents = match status {
+        PlaybackStatus::Stopped => rsx! { "⏹️ Stopped" },
+        PlaybackStatus::Buffering { percent } => rsx! {
+            span { class: "spinner" }
+            "Buffering {percent}%"
+        },
+        PlaybackStatus::Paused => rsx! { "⏸️ Paused" },
+        PlaybackStatus::Playing => rsx! { "▶️ Playing" },
+    };
+
+    rsx! {
+        output {
+            class: "playback-status {classname}",
+            role: "status",
+            {contents}
+        }
+    }
+}
+
+/// A play/pause button which shows what pressing it will do
+#[component]
+pub fn PlayPauseButton(pipeline_state: PipelineState) -> Element {
+    let commands = use_coroutine_handle::<rradio_messages::Command>();
+
+    let (icon, title) = match pipeline_state {
+        PipelineState::Playing => ("⏸️", "Pause"),
+        PipelineState::Paused => ("▶️", "Play"),
+        PipelineState::VoidPending | PipelineState::Null | PipelineState::Ready => {
+            ("⏯️", "Play/Pause")
+        }
+    };
+
+    rsx! {
+        button {
+            "type": "button",
+            title: "{title}",
+            onclick: move |_| commands.send(rradio_messages::Command::PlayPause),
+            "{icon}"
+        }
+    }
+}
+
+/// Counts down the pause before a station starts playing.
+///
+/// The server only sends the length of the pause, so the countdown is driven by a local timer,
+/// which restarts whenever a station is played, even if the pause is the same length.
+#[component]
+pub fn PauseBeforePlayingView(
+    pause_before_playing: Duration,
+    current_station: FastEqRc<rradio_messages::CurrentStation>,
+) -> Element {
+    let commands = use_coroutine_handle::<rradio_messages::Command>();
+
+    let now = use_now();
+    let mut started_at = use_signal(|| BrowserClock.now());
+
+    use_effect(use_reactive(
+        (&pause_before_playing, &current_station),
+        move |_| started_at.set(BrowserClock.now()),
+    ));
+
+    let remaining = pause_before_playing.saturating_sub(now().saturating_sub(started_at()));
+
+    let remaining_secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+
+    rsx! {
+        div {
+            class: "pause-before-playing",
+            role: "status",
+            if remaining_secs > 0 {
+                "Starting in {remaining_secs}s…"
+            } else {
+                "Starting…"
+            }
+            button {
+                "type": "button",
+                title: "Stop the station",
+                onclick: move |_| commands.send(rradio_messages::Command::Eject),
+                "Cancel"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffering_only_overrides_playing() {
+        assert_eq!(
+            PlaybackStatus::new(PipelineState::Playing, 40),
+            PlaybackStatus::Buffering { percent: 40 }
+        );
+        assert_eq!(
+            PlaybackStatus::new(PipelineState::Playing, 100),
+            PlaybackStatus::Playing
+        );
+        assert_eq!(
+            PlaybackStatus::new(PipelineState::Paused, 40),
+            PlaybackStatus::Paused
+        );
+        assert_eq!(
+            PlaybackStatus::new(PipelineState::Null, 40),
+            PlaybackStatus::Stopped
+        );
+    }
+}
@@ -2,8 +2,12 @@ use dioxus::{logger::tracing::debug, prelude::*};
 
 use crate::{
     handle_input,
-    track_position_slider::{TrackPositionSlider, TrackPositionText},
-    FastEqRc, PlayerState,
+    listen_here::{can_listen_here, ListenHereView},
+    playback_status::{
+        PauseBeforePlayingView, PlayPauseButton, PlaybackStatus, PlaybackStatusView,
+    },
+    track_position_slider::{use_track_position, TrackPositionOutput, TrackPositionSlider},
+    use_player_state_without_position, FastEqRc, PlayerState,
 };
 
 #[component]
@@ -157,7 +161,33 @@ fn StationView(
 }
 
 #[component]
-pub fn PlayerStateView(player_state: PlayerState) -> Element {
+fn MuteButton(is_muted: bool) -> Element {
+    let commands = use_coroutine_handle::<rradio_messages::Command>();
+
+    let (icon, title) = if is_muted {
+        ("🔇", "Unmute")
+    } else {
+        ("🔉", "Mute")
+    };
+
+    rsx! {
+        button {
+            id: "mute",
+            "type": "button",
+            title: "{title} (M)",
+            accesskey: "m",
+            aria_pressed: "{is_muted}",
+            onclick: move |_| commands.send(rradio_messages::Command::SetIsMuted(!is_muted)),
+            "{icon}"
+        }
+    }
+}
+
+#[component]
+pub fn PlayerStateView(player_state: Signal<PlayerState>) -> Element {
+    let track_position = use_track_position(player_state);
+    let player_state = use_player_state_without_position(player_state)();
+
     debug!(?player_state, "PlayerStateView");
 
     let commands = use_coroutine_handle::<rradio_messages::Command>();
@@ -172,8 +202,8 @@ pub fn PlayerStateView(player_state: PlayerState) -> Element {
         _ => None,
     };
 
-    let track_position =
-        TrackPositionText::new(&player_state.track_position, &player_state.track_duration);
+    let is_advancing = PlaybackStatus::new(player_state.pipeline_state, player_state.buffering)
+        == PlaybackStatus::Playing;
 
     rsx! {
         fieldset {
@@ -182,25 +212,30 @@ pub fn PlayerStateView(player_state: PlayerState) -> Element {
             {current_track}
         }
         StationView { current_station: player_state.current_station.clone(), current_track_index: player_state.current_track_index }
-        TrackPositionSlider { track_position }
+        if let Some(pause_before_playing) = player_state.pause_before_playing {
+            PauseBeforePlayingView { pause_before_playing, current_station: player_state.current_station.clone() }
+        }
+        TrackPositionSlider { track_position, is_advancing }
         footer {
             div {
                 class: "expand center-single-child",
                 div {
                     id: "time",
-                    output { "{track_position}" }
+                    PlaybackStatusView { pipeline_state: player_state.pipeline_state, buffering: player_state.buffering }
+                    TrackPositionOutput { track_position, is_advancing }
                 }
             }
             div {
                 id: "controls",
                 button { onclick: move |_| commands.send(rradio_messages::Command::SmartPreviousItem), "⏪" }
-                button { onclick: move |_| commands.send(rradio_messages::Command::PlayPause), "⏯️" }
+                PlayPauseButton { pipeline_state: player_state.pipeline_state }
                 button { onclick: move |_| commands.send(rradio_messages::Command::NextItem), "⏩" }
             }
             div {
                 class: "expand center-single-child",
                 id: "volume",
-                "🔉"
+                class: if player_state.is_muted { "muted" },
+                MuteButton { is_muted: player_state.is_muted }
                 input {
                     "type": "range",
                     min: "{volume_min}",
@@ -211,5 +246,6 @@ pub fn PlayerStateView(player_state: PlayerState) -> Element {
                 "🔊"
             }
         }
+        ListenHereView { can_play: can_listen_here(&player_state), is_server_muted: player_state.is_muted }
     }
 }
@@ -6,8 +6,10 @@ use dioxus::{logger::tracing::error, prelude::*};
 use gloo_storage::Storage;
 
 use crate::{
-    track_position_slider::{TrackPositionSlider, TrackPositionText},
-    PlayerState,
+    playback_status::{PauseBeforePlayingView, PlayPauseButton, PlaybackStatus},
+    route::{self, Route},
+    track_position_slider::{use_track_position, TrackPositionSlider},
+    use_player_state_without_position, PlayerState,
 };
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -327,11 +329,35 @@ fn RemovePodcastView(
 }
 
 #[component]
-pub fn PodcastsView(player_state: PlayerState) -> Element {
+pub fn PodcastsView(player_state: Signal<PlayerState>, podcast_url: Option<String>) -> Element {
+    let track_position = use_track_position(player_state);
+    let player_state = use_player_state_without_position(player_state)();
+
     let commands = use_coroutine_handle::<rradio_messages::Command>();
 
     let podcasts = use_signal(Podcasts::load);
-    let selected_podcast_index = use_signal(|| 0_usize);
+    let mut selected_podcast_index = use_signal(|| 0_usize);
+
+    // Follow links to a specific podcast
+    use_effect(use_reactive!(|podcast_url| {
+        if let Some(index) = podcast_url.and_then(|podcast_url| {
+            podcasts
+                .peek()
+                .iter()
+                .position(|podcast| podcast.url == podcast_url)
+        }) {
+            selected_podcast_index.set(index);
+        }
+    }));
+
+    // Keep the link up to date with the selected podcast, without adding to the browser history
+    use_effect(move || {
+        if let Some(podcast) = podcasts.get(selected_podcast_index()) {
+            route::replace(&Route::Podcasts {
+                podcast_url: Some(podcast.url.clone()),
+            });
+        }
+    });
 
     let track_title = player_state
         .current_track_tags
@@ -349,8 +375,8 @@ pub fn PodcastsView(player_state: PlayerState) -> Element {
         })
         .unwrap_or_default();
 
-    let track_position =
-        TrackPositionText::new(&player_state.track_position, &player_state.track_duration);
+    let is_advancing = PlaybackStatus::new(player_state.pipeline_state, player_state.buffering)
+        == PlaybackStatus::Playing;
 
     let seek_offset = std::time::Duration::from_secs(10);
 
@@ -364,14 +390,17 @@ pub fn PodcastsView(player_state: PlayerState) -> Element {
             }
             RemovePodcastView { podcasts, selected_podcast_index }
         }
-        TrackPositionSlider { track_position }
+        if let Some(pause_before_playing) = player_state.pause_before_playing {
+            PauseBeforePlayingView { pause_before_playing, current_station: player_state.current_station.clone() }
+        }
+        TrackPositionSlider { track_position, is_advancing }
         div {
             style: "text-align: center;",
             "{track_title}"
         }
         footer {
             button { onclick: move |_| commands.send(rradio_messages::Command::SeekBackwards(seek_offset)), "⏪" }
-            button { onclick: move |_| commands.send(rradio_messages::Command::PlayPause), "⏯️" }
+            PlayPauseButton { pipeline_state: player_state.pipeline_state }
             button { onclick: move |_| commands.send(rradio_messages::Command::SeekForwards(seek_offset)), "⏩" }
         }
     }
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use crate::track_position_slider::TrackPositionText;
+
+/// Estimates the track position between reports from the server.
+///
+/// Times are offsets from the epoch used by [`Clock::now`](crate::reconnect::Clock::now).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionInterpolator {
+    /// The most recent position reported by the server
+    reported: TrackPositionText,
+    /// The estimated position at `anchored_at`
+    anchor_position: Duration,
+    anchored_at: Duration,
+    is_advancing: bool,
+}
+
+impl PositionInterpolator {
+    pub fn new(reported: TrackPositionText, is_advancing: bool, now: Duration) -> Self {
+        Self {
+            reported,
+            anchor_position: reported_position(reported),
+            anchored_at: now,
+            is_advancing,
+        }
+    }
+
+    /// Whether the position changes over time, i.e. the track is playing
+    pub fn is_advancing(&self) -> bool {
+        self.is_advancing
+    }
+
+    /// A new report from the server replaces the estimate, correcting any drift.
+    /// Pausing or resuming continues from the current estimate.
+    pub fn update(&mut self, reported: TrackPositionText, is_advancing: bool, now: Duration) {
+        if reported != self.reported {
+            *self = Self::new(reported, is_advancing, now);
+        } else if is_advancing != self.is_advancing {
+            self.anchor_position = self.position_at(now);
+            self.anchored_at = now;
+            self.is_advancing = is_advancing;
+        }
+    }
+
+    fn position_at(&self, now: Duration) -> Duration {
+        if self.is_advancing {
+            self.anchor_position + now.saturating_sub(self.anchored_at)
+        } else {
+            self.anchor_position
+        }
+    }
+
+    /// The estimated position at `now`, which never passes the end of the track
+    pub fn track_position(&self, now: Duration) -> TrackPositionText {
+        match self.reported {
+            TrackPositionText::NoTrack => TrackPositionText::NoTrack,
+            TrackPositionText::Track { duration, .. } => TrackPositionText::Track {
+                position: self.position_at(now).min(duration),
+                duration,
+            },
+        }
+    }
+}
+
+fn reported_position(reported: TrackPositionText) -> Duration {
+    match reported {
+        TrackPositionText::NoTrack => Duration::ZERO,
+        TrackPositionText::Track { position, .. } => position,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(position: u64) -> TrackPositionText {
+        TrackPositionText::Track {
+            position: Duration::from_secs(position),
+            duration: Duration::from_secs(100),
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn advances_while_playing() {
+        let interpolator = PositionInterpolator::new(track(10), true, secs(1000));
+
+        assert_eq!(interpolator.track_position(secs(1000)), track(10));
+        assert_eq!(interpolator.track_position(secs(1005)), track(15));
+    }
+
+    #[test]
+    fn stays_still_while_paused() {
+        let interpolator = PositionInterpolator::new(track(10), false, secs(1000));
+
+        assert_eq!(interpolator.track_position(secs(1005)), track(10));
+    }
+
+    #[test]
+    fn pausing_and_resuming_continue_from_the_estimate() {
+        let mut interpolator = PositionInterpolator::new(track(10), true, secs(1000));
+
+        interpolator.update(track(10), false, secs(1005));
+        assert_eq!(interpolator.track_position(secs(1020)), track(15));
+
+        interpolator.update(track(10), true, secs(1020));
+        assert_eq!(interpolator.track_position(secs(1023)), track(18));
+    }
+
+    #[test]
+    fn new_report_corrects_drift() {
+        let mut interpolator = PositionInterpolator::new(track(10), true, secs(1000));
+
+        interpolator.update(track(13), true, secs(1005));
+
+        assert_eq!(interpolator.track_position(secs(1005)), track(13));
+        assert_eq!(interpolator.track_position(secs(1007)), track(15));
+    }
+
+    #[test]
+    fn never_passes_the_end_of_the_track() {
+        let interpolator = PositionInterpolator::new(track(95), true, secs(1000));
+
+        assert_eq!(interpolator.track_position(secs(1010)), track(100));
+    }
+
+    #[test]
+    fn no_track_has_no_position() {
+        let interpolator = PositionInterpolator::new(TrackPositionText::NoTrack, true, secs(1000));
+
+        assert_eq!(
+            interpolator.track_position(secs(1010)),
+            TrackPositionText::NoTrack
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::{convert::TryFrom, time::Duration};
+
+pub trait Clock {
+    /// The current time, as an offset from an arbitrary but fixed epoch
+    fn now(&self) -> Duration;
+
+    /// A random number in the range `0.0..1.0`, used for jitter
+    fn random(&self) -> f64;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrowserClock;
+
+impl Clock for BrowserClock {
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64(web_sys::js_sys::Date::now() / 1000.0)
+    }
+
+    fn random(&self) -> f64 {
+        web_sys::js_sys::Math::random()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+    /// The fraction of the delay which is randomised, in the range `0.0..=1.0`
+    pub jitter: f64,
+    /// Give up after this many consecutive failures, or never if `None`
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(60),
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before retry number `attempt` (starting from 0), or `None` if we should give up
+    pub fn delay(&self, attempt: u32, random: f64) -> Option<Duration> {
+        if self
+            .max_attempts
+            .is_some_and(|max_attempts| attempt >= max_attempts)
+        {
+            return None;
+        }
+
+        let max_delay = self.max_delay.as_secs_f64();
+
+        let delay = (self.initial_delay.as_secs_f64()
+            * self
+                .multiplier
+                .powi(i32::try_from(attempt).unwrap_or(i32::MAX)))
+        .min(max_delay);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+
+        let delay = delay * (1.0 - jitter * random.clamp(0.0, 1.0));
+
+        Some(Duration::from_secs_f64(delay.clamp(0.0, max_delay)))
+    }
+}
+
+pub struct Backoff<C: Clock> {
+    policy: ReconnectPolicy,
+    clock: C,
+    attempt: u32,
+}
+
+impl<C: Clock> Backoff<C> {
+    pub fn new(policy: ReconnectPolicy, clock: C) -> Self {
+        Self {
+            policy,
+            clock,
+            attempt: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    /// Returns the delay until the next attempt and the time at which it should take place
+    pub fn next_retry(&mut self) -> Option<(Duration, Duration)> {
+        let delay = self.policy.delay(self.attempt, self.clock.random())?;
+
+        self.attempt = self.attempt.saturating_add(1);
+
+        Some((delay, self.clock.now() + delay))
+    }
+}
+
+/// A clock controlled by tests, starting at 1000s
+#[cfg(test)]
+pub struct FakeClock {
+    pub now: std::cell::Cell<Duration>,
+    pub random: std::cell::Cell<f64>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new(random: f64) -> Self {
+        Self {
+            now: std::cell::Cell::new(Duration::from_secs(1000)),
+            random: std::cell::Cell::new(random),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+#[cfg(test)]
+impl Clock for &FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn random(&self) -> f64 {
+        self.random.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy_without_jitter() -> ReconnectPolicy {
+        ReconnectPolicy {
+            jitter: 0.0,
+            ..ReconnectPolicy::default()
+        }
+    }
+
+    #[test]
+    fn delay_grows_by_multiplier() {
+        let policy = policy_without_jitter();
+
+        let delays = (0..5)
+            .map(|attempt| policy.delay(attempt, 0.5))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            [1, 2, 4, 8, 16]
+                .iter()
+                .map(|&secs| Some(Duration::from_secs(secs)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        let policy = policy_without_jitter();
+
+        assert_eq!(policy.delay(6, 0.0), Some(Duration::from_secs(60)));
+        assert_eq!(policy.delay(u32::MAX, 0.0), Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy = ReconnectPolicy {
+            jitter: 0.25,
+            ..ReconnectPolicy::default()
+        };
+
+        let delay = |random| policy.delay(3, random).unwrap();
+
+        assert_eq!(delay(0.0), Duration::from_secs(8));
+        assert_eq!(delay(1.0), Duration::from_secs(6));
+        assert_eq!(delay(0.5), Duration::from_secs(7));
+
+        // Out of range random numbers and jitter are clamped
+        assert_eq!(delay(-1.0), Duration::from_secs(8));
+        assert_eq!(delay(2.0), Duration::from_secs(6));
+
+        let policy = ReconnectPolicy {
+            jitter: 2.0,
+            ..ReconnectPolicy::default()
+        };
+
+        assert_eq!(policy.delay(3, 1.0), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let policy = ReconnectPolicy {
+            max_attempts: Some(3),
+            ..policy_without_jitter()
+        };
+
+        assert!(policy.delay(2, 0.0).is_some());
+        assert_eq!(policy.delay(3, 0.0), None);
+        assert_eq!(policy.delay(4, 0.0), None);
+
+        let policy = ReconnectPolicy {
+            max_attempts: Some(0),
+            ..policy_without_jitter()
+        };
+
+        assert_eq!(policy.delay(0, 0.0), None);
+    }
+
+    #[test]
+    fn backoff_schedules_retries_from_now() {
+        let clock = FakeClock::new(0.0);
+        let mut backoff = Backoff::new(policy_without_jitter(), &clock);
+
+        assert_eq!(
+            backoff.next_retry(),
+            Some((Duration::from_secs(1), Duration::from_secs(1001)))
+        );
+
+        clock.now.set(Duration::from_secs(2000));
+
+        assert_eq!(
+            backoff.next_retry(),
+            Some((Duration::from_secs(2), Duration::from_secs(2002)))
+        );
+    }
+
+    #[test]
+    fn backoff_uses_clock_for_jitter() {
+        let clock = FakeClock::new(1.0);
+        let mut backoff = Backoff::new(ReconnectPolicy::default(), &clock);
+
+        assert_eq!(
+            backoff.next_retry().map(|(delay, _)| delay),
+            Some(Duration::from_millis(800))
+        );
+
+        clock.random.set(0.0);
+
+        assert_eq!(
+            backoff.next_retry().map(|(delay, _)| delay),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn backoff_stops_after_max_attempts_until_reset() {
+        let clock = FakeClock::new(0.0);
+        let mut backoff = Backoff::new(
+            ReconnectPolicy {
+                max_attempts: Some(2),
+                ..policy_without_jitter()
+            },
+            &clock,
+        );
+
+        assert!(backoff.next_retry().is_some());
+        assert!(backoff.next_retry().is_some());
+        assert_eq!(backoff.next_retry(), None);
+        assert_eq!(backoff.next_retry(), None);
+
+        backoff.reset();
+
+        assert_eq!(
+            backoff.next_retry().map(|(delay, _)| delay),
+            Some(Duration::from_secs(1))
+        );
+    }
+}
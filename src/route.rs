@@ -0,0 +1,273 @@
+use dioxus::{logger::tracing::error, prelude::*};
+
+use crate::AppView;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SettingsTab {
+    #[default]
+    Server,
+    SavedServers,
+    Logging,
+}
+
+impl SettingsTab {
+    pub const ALL: [Self; 3] = [Self::Server, Self::SavedServers, Self::Logging];
+
+    fn segment(self) -> &'static str {
+        match self {
+            Self::Server => "server",
+            Self::SavedServers => "servers",
+            Self::Logging => "logging",
+        }
+    }
+
+    fn from_segment(segment: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|tab| tab.segment() == segment)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Server => "Server",
+            Self::SavedServers => "Saved Servers",
+            Self::Logging => "Logging",
+        }
+    }
+}
+
+/// The route is kept in the URL fragment, e.g. `#/podcasts`, so that any static file server can serve the app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    PlayerState,
+    Podcasts { podcast_url: Option<String> },
+    Dashboard,
+    Settings { tab: SettingsTab },
+    Errors,
+    Debug,
+}
+
+impl Route {
+    /// Parse a route from the current location, as given by [`History::current_route`]
+    pub fn parse(location: &str) -> Self {
+        let fragment = location
+            .split_once('#')
+            .map_or("", |(_, fragment)| fragment);
+
+        let mut segments = fragment.split('/').filter(|segment| !segment.is_empty());
+
+        match (segments.next(), segments.next()) {
+            (Some("podcasts"), podcast_url) => Self::Podcasts {
+                podcast_url: podcast_url
+                    .and_then(|podcast_url| urlencoding::decode(podcast_url).ok())
+                    .map(std::borrow::Cow::into_owned),
+            },
+            (Some("rooms"), _) => Self::Dashboard,
+            (Some("settings"), tab) => Self::Settings {
+                tab: tab.and_then(SettingsTab::from_segment).unwrap_or_default(),
+            },
+            (Some("errors"), _) => Self::Errors,
+            (Some("debug"), _) => Self::Debug,
+            _ => Self::PlayerState,
+        }
+    }
+
+    /// The routes used before routing was added, e.g. `?podcasts`
+    fn from_legacy_query(search: &str) -> Option<Self> {
+        Some(match search {
+            "?player" => Self::PlayerState,
+            "?podcast" | "?podcasts" => Self::Podcasts { podcast_url: None },
+            "?dashboard" | "?rooms" => Self::Dashboard,
+            "?settings" => Self::Settings {
+                tab: SettingsTab::default(),
+            },
+            "?debug" => Self::Debug,
+            _ => return None,
+        })
+    }
+
+    pub fn href(&self) -> String {
+        match self {
+            Self::PlayerState => String::from("#/"),
+            Self::Podcasts { podcast_url: None } => String::from("#/podcasts"),
+            Self::Podcasts {
+                podcast_url: Some(podcast_url),
+            } => format!("#/podcasts/{}", urlencoding::encode(podcast_url)),
+            Self::Dashboard => String::from("#/rooms"),
+            Self::Settings { tab } => format!("#/settings/{}", tab.segment()),
+            Self::Errors => String::from("#/errors"),
+            Self::Debug => String::from("#/debug"),
+        }
+    }
+
+    pub fn view(&self) -> AppView {
+        match self {
+            Self::PlayerState => AppView::PlayerState,
+            Self::Podcasts { .. } => AppView::Podcasts,
+            Self::Dashboard => AppView::Dashboard,
+            Self::Settings { .. } => AppView::Settings,
+            Self::Errors => AppView::Errors,
+            Self::Debug => AppView::Debug,
+        }
+    }
+}
+
+/// Keep old links such as `?podcasts` working by replacing them with the equivalent route
+pub fn redirect_legacy_query() -> anyhow::Result<()> {
+    let location = gloo_utils::window().location();
+
+    let search = location
+        .search()
+        .map_err(|err| anyhow::anyhow!("Failed to read page location: {err:?}"))?;
+
+    let Some(route) = Route::from_legacy_query(&search) else {
+        return Ok(());
+    };
+
+    let pathname = location
+        .pathname()
+        .map_err(|err| anyhow::anyhow!("Failed to read page location: {err:?}"))?;
+
+    gloo_utils::history()
+        .replace_state_with_url(
+            &web_sys::wasm_bindgen::JsValue::NULL,
+            "",
+            Some(&format!("{pathname}{}", route.href())),
+        )
+        .map_err(|err| anyhow::anyhow!("Failed to redirect {search:?}: {err:?}"))
+}
+
+/// Navigate to `route`, adding it to the browser history
+pub fn navigate(route: &Route) {
+    if let Err(err) = gloo_utils::window().location().set_hash(&route.href()) {
+        error!("Failed to navigate to {route:?}: {err:?}");
+    }
+}
+
+/// Replace the current route without adding to the browser history or scrolling to the top of the page
+pub fn replace(route: &Route) {
+    if let Err(err) = gloo_utils::history().replace_state_with_url(
+        &web_sys::wasm_bindgen::JsValue::NULL,
+        "",
+        Some(&route.href()),
+    ) {
+        error!("Failed to replace route with {route:?}: {err:?}");
+    }
+}
+
+/// The current route. The calling component is re-rendered when the user navigates.
+pub fn use_route() -> Route {
+    use_hook(|| history().updater(schedule_update()));
+
+    Route::parse(&history().current_route())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_round_trip() {
+        let routes = vec![
+            Route::PlayerState,
+            Route::Podcasts { podcast_url: None },
+            Route::Podcasts {
+                podcast_url: Some(String::from(
+                    "https://example.com/feeds/a podcast.xml?format=rss&page=2#latest",
+                )),
+            },
+            Route::Dashboard,
+            Route::Errors,
+            Route::Debug,
+        ]
+        .into_iter()
+        .chain(SettingsTab::ALL.iter().map(|&tab| Route::Settings { tab }));
+
+        for route in routes {
+            assert_eq!(Route::parse(&route.href()), route, "{}", route.href());
+            assert_eq!(
+                Route::parse(&format!("http://rradio.local/index.html{}", route.href())),
+                route
+            );
+        }
+    }
+
+    #[test]
+    fn podcast_url_is_a_single_segment() {
+        let href = Route::Podcasts {
+            podcast_url: Some(String::from("https://example.com/feed")),
+        }
+        .href();
+
+        assert_eq!(href, "#/podcasts/https%3A%2F%2Fexample.com%2Ffeed");
+    }
+
+    #[test]
+    fn unknown_fragments_show_the_player() {
+        for location in ["", "/", "#", "#/", "#/nowhere", "#/nowhere/podcasts"] {
+            assert_eq!(Route::parse(location), Route::PlayerState, "{location}");
+        }
+    }
+
+    #[test]
+    fn empty_segments_are_ignored() {
+        assert_eq!(
+            Route::parse("#podcasts/"),
+            Route::Podcasts { podcast_url: None }
+        );
+        assert_eq!(Route::parse("#//rooms//"), Route::Dashboard);
+    }
+
+    #[test]
+    fn unknown_settings_tab_shows_the_default_tab() {
+        assert_eq!(
+            Route::parse("#/settings/nowhere"),
+            Route::Settings {
+                tab: SettingsTab::default()
+            }
+        );
+        assert_eq!(
+            Route::parse("#/settings"),
+            Route::Settings {
+                tab: SettingsTab::default()
+            }
+        );
+    }
+
+    #[test]
+    fn legacy_queries_map_to_routes() {
+        assert_eq!(
+            Route::from_legacy_query("?player"),
+            Some(Route::PlayerState)
+        );
+        assert_eq!(
+            Route::from_legacy_query("?podcast"),
+            Some(Route::Podcasts { podcast_url: None })
+        );
+        assert_eq!(
+            Route::from_legacy_query("?podcasts"),
+            Some(Route::Podcasts { podcast_url: None })
+        );
+        assert_eq!(
+            Route::from_legacy_query("?dashboard"),
+            Some(Route::Dashboard)
+        );
+        assert_eq!(Route::from_legacy_query("?rooms"), Some(Route::Dashboard));
+        assert_eq!(
+            Route::from_legacy_query("?settings"),
+            Some(Route::Settings {
+                tab: SettingsTab::default()
+            })
+        );
+        assert_eq!(Route::from_legacy_query("?debug"), Some(Route::Debug));
+    }
+
+    #[test]
+    fn other_queries_are_not_redirected() {
+        assert_eq!(Route::from_legacy_query(""), None);
+        assert_eq!(Route::from_legacy_query("?"), None);
+        assert_eq!(Route::from_legacy_query("?errors"), None);
+        assert_eq!(Route::from_legacy_query("?podcasts=1"), None);
+    }
+}
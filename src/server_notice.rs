@@ -0,0 +1,134 @@
+use dioxus::prelude::*;
+
+/// A human-readable notice sent by the server as a JSON text frame, e.g.
+/// `{"type": "maintenance", "message": "Restarting at 3am"}`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerNotice {
+    Maintenance { message: String },
+    Shutdown { message: String },
+    Error { message: String },
+}
+
+impl ServerNotice {
+    /// Returns `None` if `text` is not a known notice
+    pub fn decode(text: &str) -> Option<Self> {
+        serde_json::from_str(text).ok()
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Maintenance { .. } => "maintenance",
+            Self::Shutdown { .. } => "shutdown",
+            Self::Error { .. } => "error",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Maintenance { .. } => "Maintenance",
+            Self::Shutdown { .. } => "Server shutting down",
+            Self::Error { .. } => "Server error",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::Maintenance { message }
+            | Self::Shutdown { message }
+            | Self::Error { message } => message,
+        }
+    }
+}
+
+#[component]
+pub fn ServerNoticesView(notices: Signal<Vec<ServerNotice>>) -> Element {
+    let notices_list = notices.read();
+
+    if notices_list.is_empty() {
+        return rsx! {};
+    }
+
+    let toasts = notices_list.iter().enumerate().map(|(index, notice)| {
+        let kind = notice.kind();
+        let title = notice.title();
+        let message = notice.message();
+
+        rsx! {
+            li {
+                key: "{index}",
+                class: "{kind}",
+                strong { "{title}: " }
+                "{message}"
+                button {
+                    "type": "button",
+                    title: "Dismiss",
+                    onclick: move |_| {
+                        notices.write().remove(index);
+                    },
+                    "✖"
+                }
+            }
+        }
+    });
+
+    rsx! {
+        ul {
+            id: "server-notices",
+            {toasts}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_each_notice_type() {
+        assert_eq!(
+            ServerNotice::decode(r#"{"type": "maintenance", "message": "Restarting at 3am"}"#),
+            Some(ServerNotice::Maintenance {
+                message: String::from("Restarting at 3am")
+            })
+        );
+
+        assert_eq!(
+            ServerNotice::decode(r#"{"type": "shutdown", "message": "Goodbye"}"#),
+            Some(ServerNotice::Shutdown {
+                message: String::from("Goodbye")
+            })
+        );
+
+        assert_eq!(
+            ServerNotice::decode(r#"{"message": "No audio device", "type": "error"}"#),
+            Some(ServerNotice::Error {
+                message: String::from("No audio device")
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_unknown_type() {
+        assert_eq!(
+            ServerNotice::decode(r#"{"type": "party", "message": "Dance"}"#),
+            None
+        );
+        assert_eq!(ServerNotice::decode(r#"{"message": "Dance"}"#), None);
+    }
+
+    #[test]
+    fn ignores_non_json() {
+        assert_eq!(ServerNotice::decode("Restarting at 3am"), None);
+        assert_eq!(ServerNotice::decode(""), None);
+    }
+
+    #[test]
+    fn ignores_missing_message() {
+        assert_eq!(ServerNotice::decode(r#"{"type": "maintenance"}"#), None);
+        assert_eq!(
+            ServerNotice::decode(r#"{"type": "maintenance", "message": 3}"#),
+            None
+        );
+    }
+}
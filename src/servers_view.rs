@@ -0,0 +1,218 @@
+use dioxus::{logger::tracing::error, prelude::*};
+
+use gloo_storage::Storage;
+
+use crate::{
+    api_endpoint::ApiEndpoint,
+    route::{self, Route, SettingsTab},
+};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Server {
+    pub name: String,
+    pub address: String,
+}
+
+struct Servers;
+
+impl Servers {
+    const STORAGE_KEY: &'static str = "RRADIO_SERVERS";
+
+    fn load() -> Vec<Server> {
+        match gloo_storage::LocalStorage::get(Self::STORAGE_KEY) {
+            Ok(servers) => servers,
+            Err(gloo_storage::errors::StorageError::KeyNotFound(_)) => Vec::new(),
+            Err(err) => {
+                error!("Failed to load {}: {}", Self::STORAGE_KEY, err);
+                Vec::new()
+            }
+        }
+    }
+}
+
+trait SaveServersExt {
+    fn save(&self);
+}
+
+impl SaveServersExt for [Server] {
+    fn save(&self) {
+        if let Err(err) = gloo_storage::LocalStorage::set(Servers::STORAGE_KEY, self) {
+            error!("Failed to save servers list: {}", err);
+        }
+    }
+}
+
+/// The saved servers and the address currently connected to, shared between the server switcher and the settings page
+#[derive(Clone, Copy, PartialEq)]
+pub struct SavedServers {
+    pub servers: Signal<Vec<Server>>,
+    pub current_address: Signal<Option<String>>,
+}
+
+impl SavedServers {
+    pub fn use_context_provider() -> Self {
+        use_context_provider(|| Self {
+            servers: Signal::new(Servers::load()),
+            current_address: Signal::new(ApiEndpoint::stored_address()),
+        })
+    }
+
+    /// Save `address` as the current server and reconnect to it
+    pub fn connect_to(
+        mut self,
+        address: Option<String>,
+        commands: &mut Coroutine<rradio_messages::Command>,
+    ) -> anyhow::Result<()> {
+        ApiEndpoint::save_address(address.as_deref())?;
+
+        self.current_address.set(address);
+        commands.restart();
+
+        Ok(())
+    }
+
+    pub fn add(mut self, server: Server) {
+        let mut servers = self.servers.write();
+
+        match servers
+            .iter_mut()
+            .find(|existing| existing.name == server.name)
+        {
+            Some(existing) => *existing = server,
+            None => servers.push(server),
+        }
+
+        servers.save();
+    }
+
+    pub fn remove(mut self, index: usize) {
+        let mut servers = self.servers.write();
+
+        if index < servers.len() {
+            servers.remove(index);
+            servers.save();
+        }
+    }
+}
+
+#[component]
+pub fn ServerSelectView() -> Element {
+    let mut commands = use_coroutine_handle::<rradio_messages::Command>();
+    let saved_servers = use_context::<SavedServers>();
+
+    let servers = saved_servers.servers.read();
+
+    if servers.is_empty() {
+        return rsx! {};
+    }
+
+    let current_address = saved_servers.current_address.read();
+
+    let is_saved_server_selected = servers
+        .iter()
+        .any(|server| current_address.as_deref() == Some(server.address.as_str()));
+
+    let server_options = servers.iter().enumerate().map(|(index, server)| {
+        let is_selected = current_address.as_deref() == Some(server.address.as_str());
+        rsx! {
+            option {
+                key: "{index}",
+                selected: "{is_selected}",
+                value: "{index}",
+                "{server.name}"
+            }
+        }
+    });
+
+    rsx! {
+        select {
+            id: "server-select",
+            oninput: move |ev| {
+                let Some(server) = ev
+                    .value()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| saved_servers.servers.peek().get(index).cloned())
+                else {
+                    return;
+                };
+
+                if let Err(err) = saved_servers.connect_to(Some(server.address), &mut commands) {
+                    error!("Failed to select {}: {:#}", server.name, err);
+                }
+            },
+            option {
+                key: "other-server",
+                disabled: "disabled",
+                selected: "{!is_saved_server_selected}",
+                "Other Server"
+            }
+            {server_options}
+        }
+    }
+}
+
+#[component]
+pub fn SavedServersView(server_address: Signal<String>) -> Element {
+    let saved_servers = use_context::<SavedServers>();
+
+    let mut new_server_name = use_signal(String::new);
+
+    let add_server = move |_| {
+        let name = new_server_name.peek().trim().to_owned();
+        let address = server_address.peek().trim().to_owned();
+
+        if name.is_empty() || address.is_empty() {
+            return;
+        }
+
+        saved_servers.add(Server { name, address });
+        new_server_name.set(String::new());
+    };
+
+    let servers = saved_servers.servers.read();
+
+    let servers = servers.iter().cloned().enumerate().map(|(index, server)| {
+        rsx! {
+            li {
+                key: "{index}",
+                "{server.name} ({server.address})"
+                button {
+                    "type": "button",
+                    onclick: move |_| {
+                        server_address.set(server.address.clone());
+                        route::navigate(&Route::Settings { tab: SettingsTab::Server });
+                    },
+                    "Edit"
+                }
+                button {
+                    "type": "button",
+                    onclick: move |_| saved_servers.remove(index),
+                    "Remove"
+                }
+            }
+        }
+    });
+
+    rsx! {
+        fieldset {
+            id: "saved-servers",
+            legend { "Saved Servers" }
+            ul { {servers} }
+            label {
+                "Name: "
+                input {
+                    "type": "text",
+                    value: "{new_server_name}",
+                    oninput: move |ev| new_server_name.set(ev.value()),
+                }
+            }
+            button {
+                "type": "button",
+                disabled: new_server_name.read().trim().is_empty() || server_address.read().trim().is_empty(),
+                onclick: add_server,
+                "Save Server Address"
+            }
+        }
+    }
+}
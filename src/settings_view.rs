@@ -0,0 +1,265 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use dioxus::{logger::tracing, prelude::*};
+use futures_util::StreamExt;
+
+use crate::{
+    api_endpoint::{ApiEndpoint, Scheme},
+    link_monitor::{DEFAULT_SILENCE_TIMEOUT, SILENCE_TIMEOUT_STORAGE_KEY},
+    local_storage,
+    route::{Route, SettingsTab},
+    servers_view::{SavedServers, SavedServersView},
+    LOGGING_STORAGE_KEY,
+};
+
+const LOG_LEVELS: [tracing::Level; 5] = [
+    tracing::Level::ERROR,
+    tracing::Level::WARN,
+    tracing::Level::INFO,
+    tracing::Level::DEBUG,
+    tracing::Level::TRACE,
+];
+
+/// `None` means "use the host which served the page"
+fn parse_address(address: &str) -> anyhow::Result<Option<ApiEndpoint>> {
+    let address = address.trim();
+
+    if address.is_empty() {
+        return Ok(None);
+    }
+
+    let default_scheme = ApiEndpoint::default_scheme().unwrap_or(Scheme::Ws);
+
+    Ok(Some(ApiEndpoint::parse(address, default_scheme)?))
+}
+
+async fn test_connection(endpoint: &ApiEndpoint) -> anyhow::Result<()> {
+    let mut websocket = gloo_net::websocket::futures::WebSocket::open_with_protocol(
+        &endpoint.to_string(),
+        rradio_messages::API_VERSION_HEADER.trim(),
+    )
+    .map_err(|err| anyhow::anyhow!("Failed to open websocket: {err:?}"))?;
+
+    let first_message = futures_util::future::select(
+        websocket.next(),
+        gloo_timers::future::sleep(Duration::from_secs(5)),
+    )
+    .await;
+
+    match first_message {
+        futures_util::future::Either::Left((Some(message), _)) => {
+            match message
+                .map_err(|err| anyhow::anyhow!("Failed to receive websocket message: {err}"))?
+            {
+                gloo_net::websocket::Message::Text(message) => {
+                    anyhow::bail!("Unexpected text message: {message:?}")
+                }
+                gloo_net::websocket::Message::Bytes(mut buffer) => {
+                    rradio_messages::Event::decode(&mut buffer)
+                        .context("Failed to decode Event")?;
+                    Ok(())
+                }
+            }
+        }
+        futures_util::future::Either::Left((None, _)) => anyhow::bail!("Connection closed"),
+        futures_util::future::Either::Right(_) => anyhow::bail!("Timed out"),
+    }
+}
+
+fn parse_silence_timeout(silence_timeout: &str) -> anyhow::Result<()> {
+    match silence_timeout.trim() {
+        "" => Ok(()),
+        silence_timeout => match silence_timeout.parse::<u64>() {
+            Ok(0) | Err(_) => anyhow::bail!(
+                "Invalid silence timeout {silence_timeout:?}, expected a number of seconds"
+            ),
+            Ok(_) => Ok(()),
+        },
+    }
+}
+
+#[component]
+pub fn SettingsView(tab: SettingsTab) -> Element {
+    let mut commands = use_coroutine_handle::<rradio_messages::Command>();
+    let saved_servers = use_context::<SavedServers>();
+
+    let mut server_address = use_signal(|| ApiEndpoint::stored_address().unwrap_or_default());
+    let mut silence_timeout =
+        use_signal(|| local_storage::load(SILENCE_TIMEOUT_STORAGE_KEY).unwrap_or_default());
+    let mut log_level = use_signal(|| {
+        local_storage::load(LOGGING_STORAGE_KEY)
+            .and_then(|level| level.parse::<tracing::Level>().ok())
+            .map(|level| level.to_string())
+            .unwrap_or_default()
+    });
+    let mut status = use_signal(String::new);
+
+    let endpoint = parse_address(&server_address.read());
+    let silence_timeout_error = parse_silence_timeout(&silence_timeout.read())
+        .err()
+        .map(|err| format!("{err:#}"));
+
+    let endpoint_description = match &endpoint {
+        Ok(Some(endpoint)) => format!("Connect to {endpoint}"),
+        Ok(None) => String::from("Connect to the server which served this page"),
+        Err(err) => format!("{err:#}"),
+    };
+
+    let test_server = move |_| {
+        spawn(async move {
+            let endpoint = match parse_address(&server_address.peek())
+                .and_then(|endpoint| endpoint.map_or_else(ApiEndpoint::from_page, Ok))
+            {
+                Ok(endpoint) => endpoint,
+                Err(err) => {
+                    status.set(format!("{err:#}"));
+                    return;
+                }
+            };
+
+            status.set(format!("Testing {endpoint}..."));
+
+            status.set(match test_connection(&endpoint).await {
+                Ok(()) => format!("Successfully connected to {endpoint}"),
+                Err(err) => format!("Failed to connect to {endpoint}: {err:#}"),
+            });
+        });
+    };
+
+    let save_settings = move |_| {
+        let address = server_address.peek().trim().to_owned();
+
+        if let Err(err) = parse_address(&address) {
+            status.set(format!("{err:#}"));
+            return;
+        }
+
+        let address = Some(address).filter(|address| !address.is_empty());
+
+        let result = parse_silence_timeout(&silence_timeout.peek())
+            // Empty settings revert to the default
+            .and_then(|()| {
+                local_storage::save(
+                    SILENCE_TIMEOUT_STORAGE_KEY,
+                    Some(silence_timeout.peek().trim()).filter(|timeout| !timeout.is_empty()),
+                )
+            })
+            .and_then(|()| {
+                local_storage::save(
+                    LOGGING_STORAGE_KEY,
+                    Some(log_level.peek().as_str()).filter(|level| !level.is_empty()),
+                )
+            })
+            .and_then(|()| saved_servers.connect_to(address, &mut commands));
+
+        status.set(match result {
+            Ok(()) => String::from("Settings saved"),
+            Err(err) => format!("{err:#}"),
+        });
+    };
+
+    let log_level_options = LOG_LEVELS.iter().map(|level| {
+        let level = level.to_string();
+        let is_selected = *log_level.read() == level;
+        rsx! {
+            option {
+                key: "{level}",
+                selected: "{is_selected}",
+                value: "{level}",
+                "{level}"
+            }
+        }
+    });
+
+    let is_default_log_level = log_level.read().is_empty();
+
+    let tabs = SettingsTab::ALL.iter().map(|&other_tab| {
+        let class = if other_tab == tab { "selected" } else { "" };
+        rsx! {
+            a {
+                key: "{other_tab.title()}",
+                class: "{class}",
+                href: Route::Settings { tab: other_tab }.href(),
+                "{other_tab.title()}"
+            }
+        }
+    });
+
+    rsx! {
+        nav {
+            id: "settings-tabs",
+            {tabs}
+        }
+        if tab == SettingsTab::Server {
+            fieldset {
+                id: "server-settings",
+                legend { "Server" }
+                label {
+                    "Server Address: "
+                    input {
+                        "type": "text",
+                        placeholder: "host:port or ws://host:port/api",
+                        value: "{server_address}",
+                        oninput: move |ev| server_address.set(ev.value()),
+                    }
+                }
+                output { "{endpoint_description}" }
+                label {
+                    "Reconnect after no messages while playing for "
+                    input {
+                        "type": "number",
+                        min: "1",
+                        placeholder: "{DEFAULT_SILENCE_TIMEOUT.as_secs()}",
+                        value: "{silence_timeout}",
+                        oninput: move |ev| silence_timeout.set(ev.value()),
+                    }
+                    " seconds"
+                }
+                if let Some(silence_timeout_error) = silence_timeout_error.as_ref() {
+                    output { "{silence_timeout_error}" }
+                }
+                div {
+                    button {
+                        "type": "button",
+                        disabled: endpoint.is_err(),
+                        onclick: test_server,
+                        "Test Connection"
+                    }
+                }
+            }
+        }
+        if tab == SettingsTab::SavedServers {
+            SavedServersView { server_address }
+        }
+        if tab == SettingsTab::Logging {
+            fieldset {
+                id: "logging-settings",
+                legend { "Logging" }
+                label {
+                    "Log Level: "
+                    select {
+                        oninput: move |ev| log_level.set(ev.value()),
+                        option {
+                            key: "default",
+                            selected: "{is_default_log_level}",
+                            value: "",
+                            "Default (INFO)"
+                        }
+                        {log_level_options}
+                    }
+                }
+                output { "Changes to the log level take effect after reloading the page" }
+            }
+        }
+        footer {
+            button {
+                "type": "button",
+                disabled: endpoint.is_err() || silence_timeout_error.is_some(),
+                onclick: save_settings,
+                "Save"
+            }
+            output { "{status}" }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+
+use crate::{
+    api_endpoint::ApiEndpoint, event_recorder::EventRecorder, keyboard_shortcuts,
+    link_monitor::SILENCE_TIMEOUT_STORAGE_KEY, LOGGING_STORAGE_KEY,
+};
+
+/// Settings which might prevent the app from starting. Saved servers and podcasts are kept.
+const SETTINGS_STORAGE_KEYS: [&str; 6] = [
+    LOGGING_STORAGE_KEY,
+    ApiEndpoint::SERVER_STORAGE_KEY,
+    ApiEndpoint::URL_STORAGE_KEY,
+    SILENCE_TIMEOUT_STORAGE_KEY,
+    EventRecorder::STORAGE_KEY,
+    keyboard_shortcuts::STORAGE_KEY,
+];
+
+fn escape_html(text: &str) -> String {
+    text.chars().fold(String::new(), |mut escaped, c| {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+        escaped
+    })
+}
+
+/// Replace the app with a list of errors.
+///
+/// This is plain HTML, and the "Reset Local Settings" button uses inline javascript,
+/// so that it still works if the app has panicked.
+pub fn show(title: &str, errors: impl IntoIterator<Item = String>) {
+    let document = gloo_utils::document();
+
+    let Some(root) = document
+        .get_element_by_id("app")
+        .or_else(|| document.body().map(Into::into))
+    else {
+        return;
+    };
+
+    let mut html = format!("<main><h1>{}</h1><ul>", escape_html(title));
+
+    for error in errors {
+        let _ = write!(html, "<li><pre>{}</pre></li>", escape_html(&error));
+    }
+
+    let reset_settings = format!(
+        "[{}].forEach(function (key) {{ localStorage.removeItem(key); }}); location.reload();",
+        SETTINGS_STORAGE_KEYS
+            .iter()
+            .map(|key| format!("'{key}'"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let _ = write!(
+        html,
+        r#"</ul><button type="button" onclick="location.reload()">Reload</button><button type="button" onclick="{}">Reset Local Settings</button></main>"#,
+        escape_html(&reset_settings)
+    );
+
+    root.set_class_name("startup-error");
+    root.set_inner_html(&html);
+}
+
+/// Show panics in the page, as well as in the console
+pub fn set_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        previous_hook(info);
+
+        show("RRadio has crashed", [info.to_string()]);
+    }));
+}
@@ -1,10 +1,20 @@
-use std::{fmt, time::Duration};
+use std::{cell::Cell, convert::TryFrom, fmt, rc::Rc, time::Duration};
 
-use dioxus::prelude::*;
+use dioxus::{
+    logger::tracing::{error, warn},
+    prelude::*,
+};
 
-use crate::{handle_input, DisplayDuration};
+use crate::{
+    handle_input, local_storage,
+    position_interpolator::PositionInterpolator,
+    reconnect::{BrowserClock, Clock},
+    DisplayDuration, PlayerState,
+};
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+const INTERPOLATION_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrackPositionText {
     NoTrack,
     Track {
@@ -14,7 +24,7 @@ pub enum TrackPositionText {
 }
 
 impl TrackPositionText {
-    pub fn new(&track_position: &Option<Duration>, &track_duration: &Option<Duration>) -> Self {
+    pub fn new(track_position: Option<Duration>, track_duration: Option<Duration>) -> Self {
         track_position
             .zip(track_duration)
             .map_or(TrackPositionText::NoTrack, |(position, duration)| {
@@ -27,6 +37,15 @@ impl fmt::Display for TrackPositionText {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             TrackPositionText::NoTrack => write!(f, "--"),
+            // Both times include hours if either does, so that they line up
+            TrackPositionText::Track { position, duration } if duration.as_secs() >= 3600 => {
+                write!(
+                    f,
+                    "{:#} - {:#}",
+                    DisplayDuration(position),
+                    DisplayDuration(duration)
+                )
+            }
             TrackPositionText::Track { position, duration } => write!(
                 f,
                 "{} - {}",
@@ -37,6 +56,11 @@ impl fmt::Display for TrackPositionText {
     }
 }
 
+/// Slider values are in milliseconds, so that short tracks such as notifications can be seeked
+fn slider_value(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
 struct TrackPositionSliderValues {
     disabled: bool,
     position: u64,
@@ -53,31 +77,288 @@ impl From<TrackPositionText> for TrackPositionSliderValues {
             },
             TrackPositionText::Track { position, duration } => TrackPositionSliderValues {
                 disabled: false,
-                position: position.as_secs(),
-                duration: duration.as_secs(),
+                position: slider_value(position),
+                duration: slider_value(duration),
             },
         }
     }
 }
 
+/// The track position reported by the server.
+/// Only the components which read it are re-rendered when it changes.
+pub fn use_track_position(player_state: Signal<PlayerState>) -> Memo<TrackPositionText> {
+    use_memo(move || {
+        let player_state = player_state.read();
+        TrackPositionText::new(player_state.track_position, player_state.track_duration)
+    })
+}
+
+/// The track position reported by the server, advanced locally while `is_advancing` is set.
+/// Only the calling component is re-rendered as the position advances.
+fn use_interpolated_track_position(
+    reported: TrackPositionText,
+    is_advancing: bool,
+) -> TrackPositionText {
+    let interpolator = use_hook(|| {
+        Rc::new(Cell::new(PositionInterpolator::new(
+            reported,
+            is_advancing,
+            BrowserClock.now(),
+        )))
+    });
+
+    let mut tick = use_signal(|| 0_u32);
+
+    use_future({
+        let interpolator = interpolator.clone();
+        move || {
+            let interpolator = interpolator.clone();
+            async move {
+                loop {
+                    gloo_timers::future::sleep(INTERPOLATION_INTERVAL).await;
+                    if interpolator.get().is_advancing() {
+                        tick += 1;
+                    }
+                }
+            }
+        }
+    });
+
+    // Subscribe to the timer, but use the exact time so that new reports are anchored precisely
+    let _ = tick();
+    let now = BrowserClock.now();
+
+    let mut current = interpolator.get();
+    current.update(reported, is_advancing, now);
+    interpolator.set(current);
+
+    current.track_position(now)
+}
+
+const SHOW_REMAINING_TIME_STORAGE_KEY: &str = "RRADIO_SHOW_REMAINING_TIME";
+
+/// The time remaining in the track, e.g. `-12:04`
+struct DisplayRemainingTime(TrackPositionText);
+
+impl fmt::Display for DisplayRemainingTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            TrackPositionText::NoTrack => write!(f, "--"),
+            TrackPositionText::Track { position, duration } => {
+                let remaining = DisplayDuration(duration.saturating_sub(position));
+
+                if duration.as_secs() >= 3600 {
+                    write!(f, "-{remaining:#}")
+                } else {
+                    write!(f, "-{remaining}")
+                }
+            }
+        }
+    }
+}
+
+/// Click to switch between showing the elapsed and the remaining time
 #[component]
-pub fn TrackPositionSlider(track_position: TrackPositionText) -> Element {
+pub fn TrackPositionOutput(track_position: Memo<TrackPositionText>, is_advancing: bool) -> Element {
+    let track_position = use_interpolated_track_position(track_position(), is_advancing);
+
+    let mut show_remaining_time =
+        use_signal(|| local_storage::load_flag(SHOW_REMAINING_TIME_STORAGE_KEY));
+
+    let (text, title) = if show_remaining_time() {
+        (
+            DisplayRemainingTime(track_position).to_string(),
+            "Show elapsed time",
+        )
+    } else {
+        (track_position.to_string(), "Show remaining time")
+    };
+
+    rsx! {
+        output {
+            title: "{title}",
+            style: "cursor: pointer;",
+            onclick: move |_| {
+                let show = !show_remaining_time();
+                show_remaining_time.set(show);
+
+                if let Err(err) = local_storage::save_flag(SHOW_REMAINING_TIME_STORAGE_KEY, show) {
+                    error!("{err:#}");
+                }
+            },
+            "{text}"
+        }
+    }
+}
+
+/// A signed change in position, e.g. `+01:30` or `-00:10`
+struct DisplaySeekOffset {
+    from: Duration,
+    to: Duration,
+}
+
+impl fmt::Display for DisplaySeekOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to.checked_sub(self.from) {
+            Some(forwards) => write!(f, "+{}", DisplayDuration(forwards)),
+            None => write!(f, "-{}", DisplayDuration(self.from.saturating_sub(self.to))),
+        }
+    }
+}
+
+/// The change in position when a key is pressed while the slider is focused
+fn keyboard_step(key: &Key) -> Option<(Duration, bool)> {
+    let small_step = Duration::from_secs(5);
+    let large_step = Duration::from_secs(30);
+
+    match key {
+        Key::ArrowRight | Key::ArrowUp => Some((small_step, true)),
+        Key::ArrowLeft | Key::ArrowDown => Some((small_step, false)),
+        Key::PageUp => Some((large_step, true)),
+        Key::PageDown => Some((large_step, false)),
+        _ => None,
+    }
+}
+
+/// While the user drags the slider or holds down a key, the target position is shown instead of the track position,
+/// and a single seek is sent when they let go
+#[component]
+pub fn TrackPositionSlider(track_position: Memo<TrackPositionText>, is_advancing: bool) -> Element {
     let commands = use_coroutine_handle::<rradio_messages::Command>();
 
+    let track_position = use_interpolated_track_position(track_position(), is_advancing);
+
+    let mut scrub_target = use_signal(|| None::<Duration>);
+    // The slider value when the pointer was pressed, as a change event is only sent if the value is different
+    let mut pointer_down_value = use_signal(|| None::<u64>);
+
     let TrackPositionSliderValues {
         disabled,
         position,
         duration,
     } = TrackPositionSliderValues::from(track_position);
 
+    let current_position = Duration::from_millis(position);
+    let track_duration = Duration::from_millis(duration);
+
+    let scrub_target_value = scrub_target();
+    let value = scrub_target_value.map_or(position, slider_value);
+
+    let preview = scrub_target_value.map(|target| {
+        let offset = DisplaySeekOffset {
+            from: current_position,
+            to: target,
+        };
+        let target_percent = (100 * slider_value(target))
+            .checked_div(duration)
+            .unwrap_or(0);
+
+        rsx! {
+            output {
+                class: "seek-preview",
+                style: "left: {target_percent}%",
+                "{DisplayDuration(target)} ({offset})"
+            }
+        }
+    });
+
+    // If the slider is released where it started, there is no change event to end scrubbing
+    let mut release_pointer = move || {
+        let target_value = (*scrub_target.peek()).map(slider_value);
+
+        if target_value == pointer_down_value.take() {
+            scrub_target.set(None);
+        }
+    };
+
     rsx! {
-        input {
-            "type": "range",
-            disabled: "{disabled}",
-            min: "0",
-            max: "{duration}",
-            value: "{position}",
-            onchange: move |ev| handle_input(|new_position_secs| rradio_messages::Command::SeekTo(Duration::from_secs(new_position_secs)), &ev.value(), &commands),
+        div {
+            class: "track-position-slider",
+            {preview}
+            input {
+                "type": "range",
+                disabled: "{disabled}",
+                min: "0",
+                max: "{duration}",
+                value: "{value}",
+                oninput: move |ev| match ev.value().parse() {
+                    Ok(target_millis) => scrub_target.set(Some(Duration::from_millis(target_millis))),
+                    Err(err) => warn!("Failed to handle slider value {:?}: {err}", ev.value()),
+                },
+                onchange: move |ev| {
+                    scrub_target.set(None);
+                    handle_input(|new_position_millis| rradio_messages::Command::SeekTo(Duration::from_millis(new_position_millis)), &ev.value(), &commands);
+                },
+                onpointerdown: move |_| pointer_down_value.set(Some(value)),
+                onpointerup: move |_| release_pointer(),
+                onpointercancel: move |_| release_pointer(),
+                onblur: move |_| scrub_target.set(None),
+                onkeydown: move |ev| {
+                    if let Some((step, is_forwards)) = keyboard_step(&ev.key()) {
+                        ev.prevent_default();
+
+                        let from = scrub_target.peek().unwrap_or(current_position);
+                        let target = if is_forwards {
+                            (from + step).min(track_duration)
+                        } else {
+                            from.saturating_sub(step)
+                        };
+
+                        scrub_target.set(Some(target));
+                    }
+                },
+                onkeyup: move |ev| {
+                    if keyboard_step(&ev.key()).is_some() {
+                        if let Some(target) = scrub_target.take() {
+                            commands.send(rradio_messages::Command::SeekTo(target));
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(position: u64, duration: u64) -> TrackPositionText {
+        TrackPositionText::Track {
+            position: Duration::from_secs(position),
+            duration: Duration::from_secs(duration),
         }
     }
+
+    #[test]
+    fn remaining_time_counts_down_to_the_end() {
+        assert_eq!(DisplayRemainingTime(track(30, 95)).to_string(), "-01:05");
+        assert_eq!(DisplayRemainingTime(track(95, 95)).to_string(), "-00:00");
+        assert_eq!(DisplayRemainingTime(track(100, 95)).to_string(), "-00:00");
+    }
+
+    #[test]
+    fn remaining_time_includes_hours_if_the_track_does() {
+        assert_eq!(
+            DisplayRemainingTime(track(3000, 3700)).to_string(),
+            "-0:11:40"
+        );
+    }
+
+    #[test]
+    fn remaining_time_without_a_track() {
+        assert_eq!(
+            DisplayRemainingTime(TrackPositionText::NoTrack).to_string(),
+            "--"
+        );
+    }
+
+    #[test]
+    fn slider_values_are_milliseconds() {
+        assert_eq!(slider_value(Duration::ZERO), 0);
+        assert_eq!(slider_value(Duration::from_millis(1500)), 1500);
+        assert_eq!(slider_value(Duration::from_secs(3600)), 3_600_000);
+        assert_eq!(slider_value(Duration::from_micros(2999)), 2);
+        assert_eq!(slider_value(Duration::MAX), u64::MAX);
+    }
 }
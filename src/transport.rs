@@ -0,0 +1,429 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use anyhow::Context as _;
+use dioxus::{logger::tracing::debug, prelude::*};
+use futures_util::{ready, Sink, SinkExt, Stream, StreamExt};
+use rradio_messages::ArcStr;
+
+use crate::{
+    event_recorder::{self, EventRecorder, RecordedMessage},
+    server_notice::ServerNotice,
+};
+
+/// A message received from an rradio server
+#[derive(Debug)]
+pub enum ServerMessage {
+    Event(rradio_messages::Event),
+    Notice(ServerNotice),
+}
+
+/// A connection to an rradio server, which receives [`rradio_messages::Command`]s and produces [`ServerMessage`]s.
+/// The stream ends when the server closes the connection normally.
+pub trait Transport:
+    Sink<rradio_messages::Command, Error = anyhow::Error>
+    + Stream<Item = anyhow::Result<ServerMessage>>
+    + Unpin
+{
+}
+
+impl<T> Transport for T where
+    T: Sink<rradio_messages::Command, Error = anyhow::Error>
+        + Stream<Item = anyhow::Result<ServerMessage>>
+        + Unpin
+{
+}
+
+/// The server speaks a different version of the API to the client.
+/// `server` is `None` if the server didn't announce its API version.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Incompatible server: client API version {client:?}, server API version {server:?}")]
+pub struct IncompatibleServerError {
+    pub client: ArcStr,
+    pub server: Option<ArcStr>,
+}
+
+impl IncompatibleServerError {
+    fn new(server: Option<ArcStr>) -> Self {
+        Self {
+            client: ArcStr::from(rradio_messages::API_VERSION_HEADER.trim()),
+            server,
+        }
+    }
+}
+
+const API_VERSION_PREFIX: &str = "RRADIO-API-";
+
+/// The server announces its API version as a text message in the same format as [`rradio_messages::API_VERSION_HEADER`],
+/// e.g. `RRADIO-API-0.38.0`, so that a client can read it even if it doesn't select the client's protocol
+fn decode_api_version(text: &str) -> Option<ArcStr> {
+    let version = text.trim();
+
+    version
+        .strip_prefix(API_VERSION_PREFIX)
+        .is_some_and(|number| !number.is_empty())
+        .then(|| ArcStr::from(version))
+}
+
+/// Compare the version numbers of two API versions, e.g. `RRADIO-API-0.38.0` and `RRADIO-API-0.39.1`.
+/// Returns `None` if either isn't a dot-separated list of numbers.
+pub fn compare_api_versions(a: &str, b: &str) -> Option<std::cmp::Ordering> {
+    fn version_numbers(version: &str) -> Option<Vec<u64>> {
+        version
+            .strip_prefix(API_VERSION_PREFIX)?
+            .split('.')
+            .map(|number| number.parse().ok())
+            .collect()
+    }
+
+    Some(version_numbers(a)?.cmp(&version_numbers(b)?))
+}
+
+/// Records every [`rradio_messages::Command`] sent and [`rradio_messages::Event`] received in `recorder`
+pub struct WebSocketTransport {
+    websocket: gloo_net::websocket::futures::WebSocket,
+    recorder: Signal<EventRecorder>,
+    is_first_message: bool,
+    server_version: Option<ArcStr>,
+}
+
+/// Open a websocket and wait for the connection to be established, returning `None` if it fails
+async fn connect_websocket(
+    url: &str,
+    protocol: Option<&str>,
+) -> anyhow::Result<Option<gloo_net::websocket::futures::WebSocket>> {
+    let mut websocket = match protocol {
+        Some(protocol) => {
+            gloo_net::websocket::futures::WebSocket::open_with_protocol(url, protocol)
+        }
+        None => gloo_net::websocket::futures::WebSocket::open(url),
+    }
+    .map_err(|err| anyhow::anyhow!("Failed to open websocket: {err:?}"))?;
+
+    // Ready once the websocket is no longer connecting
+    futures_util::future::poll_fn(|cx| websocket.poll_ready_unpin(cx))
+        .await
+        .map_err(|err| anyhow::anyhow!("Failed to open websocket: {err}"))?;
+
+    Ok(matches!(websocket.state(), gloo_net::websocket::State::Open).then_some(websocket))
+}
+
+/// Wait briefly for the server to announce its API version
+async fn read_api_version(
+    websocket: &mut gloo_net::websocket::futures::WebSocket,
+) -> Option<ArcStr> {
+    let first_message = websocket.next();
+    let timeout = gloo_timers::future::sleep(std::time::Duration::from_secs(2));
+
+    futures_util::pin_mut!(timeout);
+
+    match futures_util::future::select(first_message, timeout).await {
+        futures_util::future::Either::Left((
+            Some(Ok(gloo_net::websocket::Message::Text(message))),
+            _,
+        )) => decode_api_version(&message),
+        futures_util::future::Either::Left(_) | futures_util::future::Either::Right(_) => None,
+    }
+}
+
+/// The close code sent by a server which has finished normally, e.g. because rradio has terminated
+const NORMAL_CLOSURE: u16 = 1000;
+
+/// Returns `None` if the server closed the connection normally.
+/// Any other closure, e.g. code 1006 when the network drops, is an error so that the client reconnects.
+fn close_error(close_event: &gloo_net::websocket::events::CloseEvent) -> Option<anyhow::Error> {
+    let gloo_net::websocket::events::CloseEvent {
+        code,
+        reason,
+        was_clean,
+    } = close_event;
+
+    if *was_clean && *code == NORMAL_CLOSURE {
+        return None;
+    }
+
+    Some(if reason.is_empty() {
+        anyhow::anyhow!("Connection closed unexpectedly (code {code})")
+    } else {
+        anyhow::anyhow!("Connection closed unexpectedly (code {code}): {reason}")
+    })
+}
+
+impl WebSocketTransport {
+    /// Connect to the server, which must accept the client's API version
+    pub async fn connect(url: &str, recorder: Signal<EventRecorder>) -> anyhow::Result<Self> {
+        let protocol = rradio_messages::API_VERSION_HEADER.trim();
+
+        let transport = |websocket| Self {
+            websocket,
+            recorder,
+            is_first_message: true,
+            server_version: None,
+        };
+
+        if let Some(websocket) = connect_websocket(url, Some(protocol)).await? {
+            return Ok(transport(websocket));
+        }
+
+        // The browser fails the handshake if the server doesn't select the requested protocol, without saying why.
+        // If the server accepts a connection without a protocol, it announces its API version,
+        // so a rejection can be told apart from the first attempt failing for some other reason, e.g. the server was still starting.
+        let Some(mut websocket) = connect_websocket(url, None).await? else {
+            anyhow::bail!("Failed to connect to {url}");
+        };
+
+        let server_version = read_api_version(&mut websocket).await;
+
+        if let Err(err) = websocket.close(None, None) {
+            debug!("Failed to close websocket: {err}");
+        }
+
+        if let Some(websocket) = connect_websocket(url, Some(protocol)).await? {
+            return Ok(transport(websocket));
+        }
+
+        if server_version.as_deref() == Some(protocol) {
+            anyhow::bail!("Failed to connect to {url}");
+        }
+
+        Err(IncompatibleServerError::new(server_version).into())
+    }
+
+    /// The first message must decode successfully
+    fn decode_first_message(&self, buffer: &mut [u8]) -> anyhow::Result<rradio_messages::Event> {
+        rradio_messages::Event::decode(buffer).map_err(|err| {
+            debug!("Failed to decode the first Event: {err}");
+
+            IncompatibleServerError::new(self.server_version.clone()).into()
+        })
+    }
+}
+
+impl Stream for WebSocketTransport {
+    type Item = anyhow::Result<ServerMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            return Poll::Ready(match ready!(self.websocket.poll_next_unpin(cx)) {
+                None => None,
+                Some(Err(gloo_net::websocket::WebSocketError::ConnectionClose(close_event))) => {
+                    close_error(&close_event).map(Err)
+                }
+                Some(Err(err)) => Some(Err(anyhow::anyhow!(
+                    "Failed to receive websocket message: {err}"
+                ))),
+                Some(Ok(gloo_net::websocket::Message::Text(message))) => {
+                    if let Some(server_version) = decode_api_version(&message) {
+                        self.server_version = Some(server_version);
+                        continue;
+                    }
+
+                    let Some(notice) = ServerNotice::decode(&message) else {
+                        debug!("Ignoring unknown text message: {message:?}");
+                        continue;
+                    };
+
+                    Some(Ok(ServerMessage::Notice(notice)))
+                }
+                Some(Ok(gloo_net::websocket::Message::Bytes(mut buffer))) => Some({
+                    event_recorder::record(self.recorder, || {
+                        RecordedMessage::Event(buffer.clone())
+                    });
+
+                    if std::mem::take(&mut self.is_first_message) {
+                        self.decode_first_message(&mut buffer)
+                    } else {
+                        rradio_messages::Event::decode(&mut buffer)
+                            .context("Failed to decode Event")
+                    }
+                    .map(ServerMessage::Event)
+                }),
+            });
+        }
+    }
+}
+
+impl Sink<rradio_messages::Command> for WebSocketTransport {
+    type Error = anyhow::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
+        self.websocket
+            .poll_ready_unpin(cx)
+            .map_err(|err| anyhow::anyhow!("Failed to send websocket message: {err}"))
+    }
+
+    fn start_send(
+        mut self: Pin<&mut Self>,
+        command: rradio_messages::Command,
+    ) -> anyhow::Result<()> {
+        let mut buffer = Vec::new();
+        command
+            .encode(&mut buffer)
+            .context("Failed to encode Command")?;
+
+        event_recorder::record(self.recorder, || RecordedMessage::Command(buffer.clone()));
+
+        self.websocket
+            .start_send_unpin(gloo_net::websocket::Message::Bytes(buffer))
+            .map_err(|err| anyhow::anyhow!("Failed to send websocket message: {err}"))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
+        self.websocket
+            .poll_flush_unpin(cx)
+            .map_err(|err| anyhow::anyhow!("Failed to send websocket message: {err}"))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
+        self.websocket
+            .poll_close_unpin(cx)
+            .map_err(|err| anyhow::anyhow!("Failed to close websocket: {err}"))
+    }
+}
+
+/// An in-memory [`Transport`], connected to a [`FakeServer`]
+pub struct ChannelTransport {
+    commands: futures_channel::mpsc::UnboundedSender<rradio_messages::Command>,
+    events: futures_channel::mpsc::UnboundedReceiver<anyhow::Result<ServerMessage>>,
+}
+
+/// The server end of a [`ChannelTransport`].
+/// Dropping `events` closes the connection.
+pub struct FakeServer {
+    pub commands: futures_channel::mpsc::UnboundedReceiver<rradio_messages::Command>,
+    pub events: futures_channel::mpsc::UnboundedSender<anyhow::Result<ServerMessage>>,
+}
+
+pub fn channel_transport() -> (ChannelTransport, FakeServer) {
+    let (commands_tx, commands_rx) = futures_channel::mpsc::unbounded();
+    let (events_tx, events_rx) = futures_channel::mpsc::unbounded();
+
+    (
+        ChannelTransport {
+            commands: commands_tx,
+            events: events_rx,
+        },
+        FakeServer {
+            commands: commands_rx,
+            events: events_tx,
+        },
+    )
+}
+
+impl Stream for ChannelTransport {
+    type Item = anyhow::Result<ServerMessage>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl Sink<rradio_messages::Command> for ChannelTransport {
+    type Error = anyhow::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
+        self.commands
+            .poll_ready_unpin(cx)
+            .map_err(|_| anyhow::anyhow!("Fake server has disconnected"))
+    }
+
+    fn start_send(
+        mut self: Pin<&mut Self>,
+        command: rradio_messages::Command,
+    ) -> anyhow::Result<()> {
+        self.commands
+            .start_send_unpin(command)
+            .map_err(|_| anyhow::anyhow!("Fake server has disconnected"))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
+        self.commands
+            .poll_flush_unpin(cx)
+            .map_err(|_| anyhow::anyhow!("Fake server has disconnected"))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<anyhow::Result<()>> {
+        self.commands
+            .poll_close_unpin(cx)
+            .map_err(|_| anyhow::anyhow!("Fake server has disconnected"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gloo_net::websocket::events::CloseEvent;
+
+    use super::*;
+
+    fn close_event(code: u16, reason: &str, was_clean: bool) -> CloseEvent {
+        CloseEvent {
+            code,
+            reason: String::from(reason),
+            was_clean,
+        }
+    }
+
+    #[test]
+    fn normal_closure_ends_the_stream() {
+        assert!(close_error(&close_event(NORMAL_CLOSURE, "", true)).is_none());
+    }
+
+    #[test]
+    fn abnormal_closures_are_errors() {
+        assert_eq!(
+            close_error(&close_event(1006, "", false))
+                .unwrap()
+                .to_string(),
+            "Connection closed unexpectedly (code 1006)"
+        );
+        assert_eq!(
+            close_error(&close_event(1001, "Restarting", true))
+                .unwrap()
+                .to_string(),
+            "Connection closed unexpectedly (code 1001): Restarting"
+        );
+        assert!(close_error(&close_event(NORMAL_CLOSURE, "", false)).is_some());
+    }
+
+    #[test]
+    fn decodes_announced_api_version() {
+        assert_eq!(
+            decode_api_version(rradio_messages::API_VERSION_HEADER).as_deref(),
+            Some(rradio_messages::API_VERSION_HEADER.trim())
+        );
+        assert_eq!(
+            decode_api_version("RRADIO-API-0.39.1").as_deref(),
+            Some("RRADIO-API-0.39.1")
+        );
+        assert_eq!(decode_api_version("RRADIO-API-"), None);
+        assert_eq!(
+            decode_api_version(r#"{"type": "maintenance", "message": "Restarting"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn compares_api_versions_numerically() {
+        use std::cmp::Ordering;
+
+        assert_eq!(
+            compare_api_versions("RRADIO-API-0.38.0", "RRADIO-API-0.38.0"),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            compare_api_versions("RRADIO-API-0.9.0", "RRADIO-API-0.10.0"),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_api_versions("RRADIO-API-1.0.0", "RRADIO-API-0.38.2"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_api_versions("RRADIO-API-0.38.0-beta", "RRADIO-API-0.38.0"),
+            None
+        );
+        assert_eq!(compare_api_versions("0.38.0", "RRADIO-API-0.38.0"), None);
+    }
+}